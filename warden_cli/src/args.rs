@@ -8,7 +8,7 @@ use std::ffi::OsStr;
 use crate::command::Command;
 use warden_core::path;
 
-use warden_core::config::Config;
+use warden_core::config::{redact, Config};
 
 
 fn os_path(path: &OsStr) -> PathBuf {
@@ -29,6 +29,15 @@ pub struct Args {
     #[structopt(long = "config", help = "Path to the configuration file", parse(from_os_str = "os_path"))]
     pub config: Option<PathBuf>,
 
+    #[structopt(long = "env", help = "The environment of the configuration file (WARDEN_ENV by default)")]
+    pub env: Option<String>,
+
+    #[structopt(long = "yes", short = "y", help = "Do not ask for confirmation, even if the environment requires it")]
+    pub yes: bool,
+
+    #[structopt(long = "show-secrets", help = "Do not hide the passwords of the connection URLs in the output")]
+    pub show_secrets: bool,
+
     #[structopt(
         long = "verbosity",
         short = "v",
@@ -52,9 +61,18 @@ impl Args {
         }
     }
 
+    /// The text with the passwords hidden, unless asked to show them
+    pub fn redact(&self, text: &str) -> String {
+        if self.show_secrets {
+            text.to_string()
+        } else {
+            redact::redact(text)
+        }
+    }
+
     pub fn get_config(&self) -> Result<Config, Error> {
         let cfg_path = self.config.as_ref().map(|path| PathArc::new(path));
-        let config = Config::new(&cfg_path)?;
+        let config = Config::new(&cfg_path, &self.env)?;
 
         Ok(config)
     }
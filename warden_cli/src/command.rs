@@ -45,3 +45,192 @@ impl Command {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use path_abs::{PathDir, PathFile};
+    use std::fs;
+    use std::time::Duration;
+    use warden_core::dbms::Connection as _;
+    use warden_mock::{connection, database};
+
+    /// Run the command line in the project
+    fn run(root: &PathDir, args: &[&str]) -> MainResult {
+        let config = root.join(".warden/config.yml");
+        let mut argv = vec!["warden", "--config", config.to_str().unwrap(), "--yes"];
+        argv.extend_from_slice(args);
+
+        Command::run(Args::from_iter(argv))
+    }
+
+    /// The first line of the error of the command line (the backtrace may follow)
+    fn error(root: &PathDir, args: &[&str]) -> String {
+        let error = format!("{:?}", run(root, args).unwrap_err());
+        error.lines().next().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_deploy() {
+        let root = testing::mock_project("cli-deploy", "cli_deploy");
+        let migrations = PathDir::new(root.join("migrations")).unwrap();
+
+        testing::create_migration(&migrations, "000001--first", Some("create table a (id int);"));
+        testing::create_migration(&migrations, "000002--second", Some("create table b (id int);"));
+
+        run(&root, &["migration", "list"]).unwrap();
+        run(&root, &["app", "status"]).unwrap();
+        run(&root, &["app", "deploy"]).unwrap();
+        run(&root, &["app", "status"]).unwrap();
+
+        // nothing left to deploy
+        run(&root, &["app", "deploy"]).unwrap();
+
+        root.remove_all().unwrap();
+
+        let database = database::get("cli_deploy");
+        let database = database.lock().unwrap();
+
+        let deployed: Vec<u128> = database
+            .get_deployments()
+            .iter()
+            .map(|deployment| deployment.meta.get_identity().get_id().unwrap())
+            .collect();
+
+        assert_eq!(deployed, [0, 1, 2]);
+        assert!(database.get_deployments().iter().all(|deployment| deployment.error.is_none()));
+        assert!(database.get_migrations().values().all(|migration| migration.deployed_at.is_some()));
+    }
+
+    #[test]
+    fn test_deploy_failure() {
+        let root = testing::mock_project("cli-deploy-failure", "cli_deploy_failure?fail=000002");
+        let migrations = PathDir::new(root.join("migrations")).unwrap();
+
+        testing::create_migration(&migrations, "000001--first", Some("create table a (id int);"));
+        testing::create_migration(&migrations, "000002--second", Some("create table b (id int);"));
+        testing::create_migration(&migrations, "000003--third", Some("create table c (id int);"));
+
+        assert!(run(&root, &["app", "deploy"]).is_err());
+        run(&root, &["app", "status"]).unwrap();
+
+        root.remove_all().unwrap();
+
+        let database = database::get("cli_deploy_failure");
+        let database = database.lock().unwrap();
+
+        // the deployment stops at the failed migration
+        assert_eq!(database.get_deployments().len(), 3);
+        assert!(database.get_deployments()[2].error.is_some());
+        assert!(database.get_migration(1).unwrap().deployed_at.is_some());
+        assert!(database.get_migration(2).is_none());
+        assert!(database.get_migration(3).is_none());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let root = testing::incapable_mock_project("cli-unsupported", "cli_unsupported");
+        let migrations = PathDir::new(root.join("migrations")).unwrap();
+
+        testing::create_migration(&migrations, "000001--first", Some("create table a (id int);"));
+        let meta = testing::create_migration(&migrations, "000002--second", Some("create index concurrently b on a (id);"));
+
+        PathFile::new(migrations.join("000002--second/meta.yml"))
+            .unwrap()
+            .append_str("transaction: 'none'\n")
+            .unwrap();
+
+        let refused = |args: &[&str], message: &str| {
+            assert_eq!(error(&root, args), format!("The \"mock-incapable\" driver does not support {}", message));
+        };
+
+        refused(&["app", "deploy", "--atomic"], "transactional DDL, --atomic is unavailable");
+        refused(
+            &["app", "deploy", "--lock-timeout", "5"],
+            "advisory locking, --lock-timeout and --no-wait are unavailable",
+        );
+        refused(
+            &["app", "deploy"],
+            &format!(
+                "non-transactional execution, migration \"{}\" (transaction: none) cannot be deployed",
+                meta.get_identity()
+            ),
+        );
+        refused(&["app", "rollback"], "revert, the migrations cannot be rolled back");
+        refused(&["migration", "restore", "000001"], "snapshot storage, the migrations cannot be restored");
+
+        // the capabilities are listed along with the status
+        run(&root, &["app", "status"]).unwrap();
+
+        root.remove_all().unwrap();
+
+        // nothing has been deployed, not even the migrations preceding the refused one
+        assert!(database::get("cli_unsupported").lock().unwrap().get_deployments().is_empty());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let root = testing::mock_project("cli-rollback", "cli_rollback");
+        let migrations = PathDir::new(root.join("migrations")).unwrap();
+
+        for (token, table) in &[("000001--first", "a"), ("000002--second", "b"), ("000003--third", "c")] {
+            testing::create_migration_with_revert(
+                &migrations,
+                token,
+                Some(&format!("create table {} (id int);", table)),
+                Some(&format!("drop table {};", table)),
+            );
+        }
+
+        run(&root, &["app", "deploy"]).unwrap();
+
+        let deployed = || -> Vec<u128> {
+            let database = database::get("cli_rollback");
+            let database = database.lock().unwrap();
+
+            database
+                .get_migrations()
+                .iter()
+                .filter(|(_, migration)| migration.deployed_at.is_some())
+                .map(|(id, _)| *id)
+                .collect()
+        };
+
+        // the lock is held by a deployment
+        let holder = connection::open("mock://cli_rollback", "warden").unwrap();
+        holder.lock(None).unwrap();
+        assert!(error(&root, &["app", "rollback", "--no-wait"]).starts_with("Could not acquire the lock"));
+        drop(holder);
+
+        // every migration to revert must be found, or nothing is reverted
+        let second = migrations.join("000002--second");
+        let moved = root.join("000002--second");
+        fs::rename(&second, &moved).unwrap();
+        assert_eq!(
+            error(&root, &["app", "rollback", "--to", "000001"]),
+            "Could not find the deployed migration \"000002\" in the repository"
+        );
+        fs::rename(&moved, &second).unwrap();
+
+        let meta = PathFile::new(migrations.join("000003--third/meta.yml")).unwrap();
+        let content = meta.read_string().unwrap();
+        meta.write_str("identity: [").unwrap();
+        assert!(run(&root, &["app", "rollback", "--to", "000001"]).is_err());
+        meta.write_str(&content).unwrap();
+
+        assert_eq!(deployed(), [0, 1, 2, 3]);
+
+        run(&root, &["app", "rollback", "--to", "000001"]).unwrap();
+        assert_eq!(deployed(), [0, 1]);
+
+        run(&root, &["app", "rollback", "--no-wait"]).unwrap();
+        assert_eq!(deployed(), [0]);
+
+        root.remove_all().unwrap();
+
+        // the lock is released
+        let connection = connection::open("mock://cli_rollback", "warden").unwrap();
+        assert!(connection.lock(Some(Duration::from_secs(0))).is_ok());
+    }
+}
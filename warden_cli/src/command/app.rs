@@ -1,15 +1,27 @@
+mod action_log;
 mod create;
 mod deploy;
+mod history;
+mod rollback;
 mod status;
+mod upgrade;
+mod verify;
+mod wait;
 
 use crate::args::Args;
 use crate::command;
 use crate::MainResult;
+use warden_core::config::Config;
+use warden_core::dbms::{ActionLogFilter, HistoryFilter};
 use warden_core::path::relpath;
 
+use chrono::prelude::{DateTime, FixedOffset};
+use failure::Error;
 use log;
 use path_abs::{PathArc, PathDir};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -31,7 +43,124 @@ pub enum Command {
         about = "Deploy the application",
         raw(setting = "structopt::clap::AppSettings::ColoredHelp")
     )]
-    Deploy,
+    Deploy {
+        #[structopt(long = "to", help = "Deploy migrations up to (and including) this one")]
+        to: Option<String>,
+
+        #[structopt(
+            long = "allow-out-of-order",
+            help = "Deploy only the missing migrations older than the last deployed one (all the pending ones up to --to if given)"
+        )]
+        allow_out_of_order: bool,
+
+        #[structopt(
+            long = "skip-seal-check",
+            help = "Do not check the migrations match their seals (recorded in the action log)"
+        )]
+        skip_seal_check: bool,
+
+        #[structopt(
+            long = "lock-timeout",
+            help = "Give up if the deployment lock cannot be acquired in this many seconds"
+        )]
+        lock_timeout: Option<u64>,
+
+        #[structopt(
+            long = "no-wait",
+            help = "Give up immediately if another deployment holds the lock",
+            raw(conflicts_with = "\"lock_timeout\"")
+        )]
+        no_wait: bool,
+
+        #[structopt(
+            long = "atomic",
+            help = "Deploy all the migrations in a single transaction, rolling everything back on failure"
+        )]
+        atomic: bool,
+
+        #[structopt(long = "wait", help = "Wait for the database to become reachable before deploying")]
+        wait: bool,
+
+        #[structopt(
+            long = "wait-timeout",
+            help = "Give up waiting for the database after this long, e.g. 60s (the default) or 5m",
+            parse(try_from_str = "wait::parse_timeout"),
+            raw(requires = "\"wait\"")
+        )]
+        wait_timeout: Option<Duration>,
+    },
+
+    #[structopt(
+        name = "history",
+        about = "List the migrations registered in the database",
+        raw(setting = "structopt::clap::AppSettings::ColoredHelp")
+    )]
+    History {
+        #[structopt(
+            long = "since",
+            help = "Only the migrations registered or deployed since the date (YYYY-MM-DD or RFC 3339)",
+            parse(try_from_str = "history::parse_since")
+        )]
+        since: Option<DateTime<FixedOffset>>,
+
+        #[structopt(long = "pending", help = "Only the migrations that have not been deployed")]
+        pending: bool,
+
+        #[structopt(long = "limit", help = "Only this many latest migrations")]
+        limit: Option<u32>,
+    },
+
+    #[structopt(
+        name = "log",
+        about = "Print the action log of the database",
+        raw(setting = "structopt::clap::AppSettings::ColoredHelp")
+    )]
+    Log {
+        #[structopt(
+            long = "since",
+            help = "Only the records since the date (YYYY-MM-DD or RFC 3339)",
+            parse(try_from_str = "history::parse_since")
+        )]
+        since: Option<DateTime<FixedOffset>>,
+
+        #[structopt(long = "user", help = "Only the actions of the database user")]
+        user: Option<String>,
+
+        #[structopt(long = "grep", help = "Only the actions matching the regular expression")]
+        grep: Option<String>,
+
+        #[structopt(long = "follow", short = "f", help = "Keep polling for the new records")]
+        follow: bool,
+
+        #[structopt(long = "json", help = "Print the records as JSON lines")]
+        json: bool,
+    },
+
+    #[structopt(
+        name = "rollback",
+        about = "Revert deployed migrations",
+        raw(setting = "structopt::clap::AppSettings::ColoredHelp")
+    )]
+    Rollback {
+        #[structopt(
+            long = "to",
+            help = "Revert every migration deployed after this one (only the last one by default)"
+        )]
+        to: Option<String>,
+
+        #[structopt(
+            long = "lock-timeout",
+            help = "Give up if the deployment lock cannot be acquired in this many seconds"
+        )]
+        lock_timeout: Option<u64>,
+
+        #[structopt(
+            long = "no-wait",
+            help = "Give up immediately if a deployment holds the lock",
+            raw(conflicts_with = "\"lock_timeout\"")
+        )]
+        no_wait: bool,
+    },
 
     #[structopt(
         name = "status",
@@ -39,14 +168,112 @@ pub enum Command {
         raw(setting = "structopt::clap::AppSettings::ColoredHelp")
     )]
     Status,
+
+    #[structopt(
+        name = "upgrade",
+        about = "Upgrade the warden schema of the database",
+        raw(setting = "structopt::clap::AppSettings::ColoredHelp")
+    )]
+    Upgrade,
+
+    #[structopt(
+        name = "verify",
+        about = "Compare the migrations registered in the database with the repository",
+        raw(setting = "structopt::clap::AppSettings::ColoredHelp")
+    )]
+    Verify,
+
+    #[structopt(
+        name = "wait",
+        about = "Wait for the database to become reachable",
+        raw(setting = "structopt::clap::AppSettings::ColoredHelp")
+    )]
+    Wait {
+        #[structopt(
+            long = "timeout",
+            help = "Give up after this long, e.g. 60s (the default) or 5m",
+            parse(try_from_str = "wait::parse_timeout")
+        )]
+        timeout: Option<Duration>,
+    },
 }
 
 impl Command {
     pub fn run(&self, args: &Args) -> MainResult {
         match self {
             Command::Create { driver, path } => self.create(driver, path.as_path()),
-            Command::Deploy => deploy::run(args),
+            Command::Deploy {
+                to,
+                allow_out_of_order,
+                skip_seal_check,
+                lock_timeout,
+                no_wait,
+                atomic,
+                wait,
+                wait_timeout,
+            } => deploy::run(
+                args,
+                to,
+                *allow_out_of_order,
+                *skip_seal_check,
+                if *no_wait {
+                    Some(Duration::from_secs(0))
+                } else {
+                    lock_timeout.map(Duration::from_secs)
+                },
+                *atomic,
+                if *wait {
+                    Some(wait_timeout.unwrap_or(wait::DEFAULT_TIMEOUT))
+                } else {
+                    None
+                },
+            ),
+            Command::History {
+                since,
+                pending,
+                limit,
+            } => history::run(
+                args,
+                HistoryFilter {
+                    since: *since,
+                    pending: *pending,
+                    limit: *limit,
+                },
+            ),
+            Command::Log {
+                since,
+                user,
+                grep,
+                follow,
+                json,
+            } => action_log::run(
+                args,
+                ActionLogFilter {
+                    since: *since,
+                    user: user.clone(),
+                    grep: grep.clone(),
+                    after_id: None,
+                },
+                *follow,
+                *json,
+            ),
+            Command::Rollback {
+                to,
+                lock_timeout,
+                no_wait,
+            } => rollback::run(
+                args,
+                to,
+                if *no_wait {
+                    Some(Duration::from_secs(0))
+                } else {
+                    lock_timeout.map(Duration::from_secs)
+                },
+            ),
             Command::Status => status::run(args),
+            Command::Upgrade => upgrade::run(args),
+            Command::Verify => verify::run(args),
+            Command::Wait { timeout } => wait::run(args, timeout.unwrap_or(wait::DEFAULT_TIMEOUT)),
         }
     }
 
@@ -59,6 +286,29 @@ impl Command {
     }
 }
 
+/// Ask the user to go on with the action, if the environment requires it
+fn confirm(args: &Args, config: &Config, action: &str) -> Result<(), Error> {
+    if !config.require_confirmation || args.yes {
+        return Ok(());
+    }
+
+    let env = config.env.as_deref().unwrap_or("default");
+
+    print!("{} (environment \"{}\")? [y/N] ", action, env);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    match answer.trim() {
+        "y" | "Y" | "yes" => Ok(()),
+        _ => Err(failure::err_msg(format!(
+            "Not confirmed, the environment \"{}\" requires confirmation (use --yes to skip it)",
+            env
+        ))),
+    }
+}
+
 pub fn run(args: Args) -> MainResult {
     match args.command {
         command::Command::App { ref command } => command.run(&args),
@@ -0,0 +1,117 @@
+use crate::args::Args;
+use crate::grid::{Grid, Grid4};
+use crate::MainResult;
+
+use chrono::prelude::Local;
+use colored::*;
+use serde_json::json;
+use std::thread;
+use std::time::Duration;
+
+use warden_core::dbms::{ActionLogEntry, ActionLogFilter};
+
+/// How often to check for the new records with --follow
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn run(args: &Args, mut filter: ActionLogFilter, follow: bool, json: bool) -> MainResult {
+    let config = args.get_config()?;
+    let conn = config.get_dbms_connection()?;
+
+    loop {
+        let records = conn.get_action_log(&filter)?;
+
+        if let Some(last) = records.last() {
+            filter.after_id = Some(last.id);
+        }
+
+        if json {
+            for record in &records {
+                println!("{}", to_json(record));
+            }
+        } else if !records.is_empty() {
+            let mut grid: Grid4 = Grid::default();
+
+            for record in &records {
+                grid.row(row(record));
+            }
+
+            print!("{}", grid.display());
+        }
+
+        if !follow {
+            break;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    Ok(())
+}
+
+fn row(record: &ActionLogEntry) -> [ColoredString; 4] {
+    [
+        format!(
+            " {}",
+            record
+                .ts
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+        )
+        .color("blue"),
+        record.user.color("green"),
+        format!(
+            "{} ({})",
+            record
+                .client_address
+                .as_ref()
+                .map(String::as_str)
+                .unwrap_or("local"),
+            record.session_pid
+        )
+        .color("white"),
+        record.action.color(""),
+    ]
+}
+
+/// A single line JSON object of the record
+fn to_json(record: &ActionLogEntry) -> String {
+    json!({
+        "id": record.id,
+        "ts": record.ts.to_rfc3339(),
+        "user": record.user,
+        "client_address": record.client_address,
+        "session_pid": record.session_pid,
+        "action": record.action,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::{FixedOffset, TimeZone};
+
+    #[test]
+    fn test_to_json() {
+        let mut record = ActionLogEntry {
+            id: 7,
+            ts: FixedOffset::east(3600).ymd(2019, 3, 4).and_hms(5, 6, 7),
+            user: "deploy".to_string(),
+            client_address: None,
+            session_pid: 4242,
+            action: "Deployed | 3tgu50--a".to_string(),
+        };
+
+        assert_eq!(
+            to_json(&record),
+            r#"{"action":"Deployed | 3tgu50--a","client_address":null,"id":7,"session_pid":4242,"ts":"2019-03-04T05:06:07+01:00","user":"deploy"}"#
+        );
+
+        record.client_address = Some("10.0.0.1".to_string());
+        record.action = "Error: \"a\\b\"\n\tnext\u{1}".to_string();
+
+        let json = to_json(&record);
+        assert!(json.contains(r#""client_address":"10.0.0.1""#));
+        assert!(json.starts_with(r#"{"action":"Error: \"a\\b\"\n\tnext\u0001","#));
+    }
+}
@@ -1,16 +1,72 @@
 use crate::args::Args;
 use crate::grid::{Grid, Grid3};
 use crate::MainResult;
-use path_abs::PathFile;
+use colored::*;
+use failure::Error;
+use path_abs::{PathDir, PathFile};
+use std::collections::BTreeSet;
+use std::time::Duration;
+use warden_core::config::Config;
+use warden_core::dbms::capabilities::unsupported;
+use warden_core::dbms::Connection;
+use warden_core::migration::transaction::TransactionMode;
 use warden_core::migration::{self, identity::Identity, meta::Meta};
 
-pub fn run(args: &Args) -> MainResult {
+pub fn run(
+    args: &Args,
+    to: &Option<String>,
+    allow_out_of_order: bool,
+    skip_seal_check: bool,
+    lock_timeout: Option<Duration>,
+    atomic: bool,
+    wait: Option<Duration>,
+) -> MainResult {
     let config = args.get_config()?;
-    let conn = config.get_dbms_connection()?;
+
+    let driver = config.driver.name();
+    let capabilities = config.driver.capabilities();
+
+    if atomic && !capabilities.transactional_ddl {
+        Err(unsupported(driver, "transactional DDL", "--atomic is unavailable"))?
+    }
+
+    if !capabilities.advisory_locking {
+        if lock_timeout.is_some() {
+            Err(unsupported(
+                driver,
+                "advisory locking",
+                "--lock-timeout and --no-wait are unavailable",
+            ))?
+        }
+
+        log::warn!(
+            "The \"{}\" driver does not support advisory locking, concurrent deployments are not prevented",
+            driver
+        );
+    }
+
+    super::confirm(args, &config, "Deploy the migrations")?;
+
+    let conn = match wait {
+        Some(timeout) => super::wait::connect(&config, timeout)?,
+        None => config.get_dbms_connection()?,
+    };
+
+    if capabilities.advisory_locking {
+        // the lock is released along with the session in case of errors
+        conn.lock(lock_timeout)?;
+    }
 
     let last_deployed = conn.get_last_deployed_migration()?;
+    let deployed = conn.get_deployed_migrations()?;
     // let last_deployed_uid = last_deployed.clone().map(base36::encode);
 
+    let to = if let Some(pattern) = to {
+        Some(target(&config.migrations, pattern, &deployed)?)
+    } else {
+        None
+    };
+
     let mut to_be_deployed = Vec::new();
 
     migration::fs::foreach_migration_sorted::<_, ()>(&config.migrations, |dir| {
@@ -20,11 +76,15 @@ pub fn run(args: &Args) -> MainResult {
             return None;
         };
 
-        if let Some(last_deployed) = last_deployed {
-            if let Some(id) = identity.get_id() {
-                if last_deployed >= id {
-                    return None;
-                }
+        if let Some(id) = identity.get_id() {
+            if deployed.contains(&id) {
+                return None;
+            }
+        }
+
+        if let Some(to) = to {
+            if identity.get_id().map(|id| id > to).unwrap_or(true) {
+                return Some(());
             }
         }
 
@@ -56,11 +116,77 @@ pub fn run(args: &Args) -> MainResult {
         None
     });
 
+    let out_of_order = out_of_order(to_be_deployed.iter().map(Meta::get_identity), last_deployed);
+
+    if !out_of_order.is_empty() && !allow_out_of_order {
+        let mut msg = format!(
+            "Migrations older than the last deployed one ({}) have not been deployed:",
+            migration::base36::encode(last_deployed.unwrap_or(0))
+        );
+        for identity in &out_of_order {
+            msg.push_str(&format!("\n - {}", identity));
+        }
+        msg.push_str("\nUse --allow-out-of-order to deploy them");
+        Err(failure::err_msg(msg))?
+    }
+
+    // exactly the missing ones, unless told how far to go
+    if allow_out_of_order && to.is_none() {
+        let missing: BTreeSet<u128> = out_of_order.iter().filter_map(Identity::get_id).collect();
+
+        to_be_deployed.retain(|meta| matches!(meta.get_identity().get_id(), Some(id) if missing.contains(&id)));
+    }
+
+    if !capabilities.non_transactional {
+        if let Some(meta) = to_be_deployed
+            .iter()
+            .find(|meta| meta.get_transaction() == TransactionMode::None)
+        {
+            Err(unsupported(
+                driver,
+                "non-transactional execution",
+                &format!(
+                    "migration \"{}\" (transaction: none) cannot be deployed",
+                    meta.get_identity()
+                ),
+            ))?
+        }
+    }
+
+    if skip_seal_check {
+        log::warn!("Skipping the seal check");
+
+        // recorded up front, so that failed deployments leave the record as well
+        if !to_be_deployed.is_empty() {
+            let uids: Vec<String> = to_be_deployed
+                .iter()
+                .map(|meta| format!("{}", meta.get_identity()))
+                .collect();
+
+            conn.log_action(&format!("Deploying without seal check | {}", uids.join(", ")))?;
+        }
+    } else {
+        for meta in &to_be_deployed {
+            meta.verify_seal()?;
+        }
+    }
+
+    if atomic {
+        deploy_atomic(&*conn, &config, to_be_deployed)?;
+
+        if capabilities.advisory_locking {
+            conn.unlock()?;
+        }
+
+        return Ok(());
+    }
+
     let mut grid: Grid3 = Grid::default();
 
     for meta in to_be_deployed {
         let uid = &format!("{}", meta.get_identity());
-        let result = conn.deploy(meta);
+        let timeouts = config.timeouts.for_migration(&meta);
+        let result = conn.deploy(meta, &timeouts);
         grid.row([" -", uid, if result.is_ok() { "[x]" } else { "[error!]" }]);
 
         if result.is_err() {
@@ -69,6 +195,174 @@ pub fn run(args: &Args) -> MainResult {
         }
     }
 
+    print!("{}", grid.display());
+
+    if capabilities.advisory_locking {
+        conn.unlock()?;
+    }
+
+    Ok(())
+}
+
+/// Deploy the migrations in a single transaction.
+/// Prints the grid with the failed migration highlighted if any
+fn deploy_atomic(
+    conn: &Connection,
+    config: &Config,
+    to_be_deployed: Vec<Meta>,
+) -> Result<(), Error> {
+    let uids: Vec<String> = to_be_deployed
+        .iter()
+        .map(|meta| format!("{}", meta.get_identity()))
+        .collect();
+
+    let result = conn.deploy_atomic(to_be_deployed, &config.timeouts);
+
+    let mut grid: Grid3 = Grid::default();
+
+    if let Err((failed, error)) = result {
+        for (position, uid) in uids.iter().enumerate() {
+            let (status, color) = atomic_status(position, failed);
+            let status = status.color(color);
+
+            let uid = if position == failed {
+                uid.color("red")
+            } else {
+                uid.color("")
+            };
+
+            grid.row([" -".color(""), uid, status]);
+        }
+
+        print!("{}", grid.display());
+        Err(error)?
+    }
+
+    for uid in &uids {
+        grid.row([" -", uid, "[x]"]);
+    }
+
     print!("{}", grid.display());
     Ok(())
 }
+
+/// The status (and its color) of the migration at the position
+/// when the one at the failed position fails
+fn atomic_status(position: usize, failed: usize) -> (&'static str, &'static str) {
+    if position < failed {
+        ("[rolled back]", "yellow")
+    } else if position == failed {
+        ("[error!]", "red")
+    } else {
+        ("[skipped]", "")
+    }
+}
+
+/// The pending migrations older than the last deployed one
+fn out_of_order<'a, I>(pending: I, last_deployed: Option<u128>) -> Vec<Identity>
+where
+    I: IntoIterator<Item = &'a Identity>,
+{
+    let last_deployed = match last_deployed {
+        Some(last_deployed) => last_deployed,
+        None => return Vec::new(),
+    };
+
+    pending
+        .into_iter()
+        .filter(|identity| matches!(identity.get_id(), Some(id) if id < last_deployed))
+        .cloned()
+        .collect()
+}
+
+/// Resolve the pattern into the ID of the last migration to be deployed
+fn target(migrations: &PathDir, pattern: &str, deployed: &BTreeSet<u128>) -> Result<u128, Error> {
+    let identity = migration::fs::lookup_pattern(migrations, pattern).ok_or(
+        failure::err_msg(format!(
+            "could not determine the migration with pattern \"{}\"",
+            pattern
+        )),
+    )?;
+
+    let id = identity
+        .get_id()
+        .ok_or(failure::err_msg("could not decode migration id"))?;
+
+    let meta = Meta::open(PathFile::new(
+        migrations.join(format!("{}", &identity)).join("meta.yml"),
+    )?)?;
+
+    if PathFile::new(meta.get_seal_meta().get_file()).is_err() {
+        Err(failure::err_msg(format!(
+            "Migration \"{}\" has not been sealed",
+            identity
+        )))?
+    }
+
+    if deployed.contains(&id) {
+        Err(failure::err_msg(format!(
+            "Migration \"{}\" has already been deployed",
+            identity
+        )))?
+    }
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn identity(token: &str) -> Identity {
+        Identity::from_str(token).unwrap()
+    }
+
+    #[test]
+    fn test_atomic_status() {
+        let statuses: Vec<&str> = (0..4).map(|position| atomic_status(position, 2).0).collect();
+        assert_eq!(statuses, ["[rolled back]", "[rolled back]", "[error!]", "[skipped]"]);
+
+        assert_eq!(atomic_status(0, 0), ("[error!]", "red"));
+        assert_eq!(atomic_status(1, 0), ("[skipped]", ""));
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let pending = vec![identity("000002--b"), identity("000004--d"), identity("000006--f")];
+
+        let ids = |identities: Vec<Identity>| -> Vec<u128> {
+            identities.iter().filter_map(Identity::get_id).collect()
+        };
+
+        assert_eq!(ids(out_of_order(&pending, Some(5))), vec![2, 4]);
+        assert_eq!(ids(out_of_order(&pending, Some(1))), Vec::<u128>::new());
+        assert_eq!(ids(out_of_order(&pending, Some(6))), vec![2, 4]);
+        assert_eq!(ids(out_of_order(&pending, None)), Vec::<u128>::new());
+    }
+
+    #[test]
+    fn test_target() {
+        let migrations = testing::temp_dir("deploy-target");
+
+        testing::create_migration(&migrations, "000001--first", Some("select 1;"));
+        testing::create_migration(&migrations, "000002--second", Some("select 2;"));
+        testing::create_migration(&migrations, "000003--draft", None);
+
+        let deployed: BTreeSet<u128> = vec![1].into_iter().collect();
+
+        let second = target(&migrations, "second", &deployed);
+        let by_uid = target(&migrations, "000002", &deployed);
+        let deployed_already = target(&migrations, "first", &deployed);
+        let not_sealed = target(&migrations, "draft", &deployed);
+        let unknown = target(&migrations, "missing", &deployed);
+
+        migrations.remove_all().unwrap();
+
+        assert_eq!(second.unwrap(), 2);
+        assert_eq!(by_uid.unwrap(), 2);
+        assert!(deployed_already.unwrap_err().to_string().contains("has already been deployed"));
+        assert!(not_sealed.unwrap_err().to_string().contains("has not been sealed"));
+        assert!(unknown.unwrap_err().to_string().contains("could not determine the migration"));
+    }
+}
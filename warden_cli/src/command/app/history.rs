@@ -0,0 +1,121 @@
+use crate::args::Args;
+use crate::grid::{Grid, Grid5};
+use crate::MainResult;
+
+use chrono::prelude::{DateTime, FixedOffset, Local, NaiveDate, TimeZone};
+use colored::*;
+use failure::Error;
+
+use warden_core::dbms::{HistoryEntry, HistoryFilter};
+
+/// How many bytes of the seal are shown
+const FINGERPRINT_LEN: usize = 6;
+
+pub fn run(args: &Args, filter: HistoryFilter) -> MainResult {
+    let config = args.get_config()?;
+    let conn = config.get_dbms_connection()?;
+
+    let history = conn.get_history(&filter)?;
+
+    let mut grid: Grid5 = Grid::default();
+
+    grid.row([
+        " Migration".color("cyan").bold(),
+        "Registered".color("cyan").bold(),
+        "Deployed".color("cyan").bold(),
+        "Seal".color("cyan").bold(),
+        "".color(""),
+    ]);
+
+    for entry in &history {
+        grid.row(row(entry));
+    }
+
+    print!("{}", grid.display());
+
+    Ok(())
+}
+
+/// Parse the --since value, either RFC 3339 or a date (midnight UTC)
+pub fn parse_since(value: &str) -> Result<DateTime<FixedOffset>, Error> {
+    if let Ok(since) = DateTime::parse_from_rfc3339(value) {
+        return Ok(since);
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        failure::err_msg(format!(
+            "\"{}\" is neither a date (YYYY-MM-DD) nor an RFC 3339 timestamp",
+            value
+        ))
+    })?;
+
+    Ok(FixedOffset::east(0).from_utc_datetime(&date.and_hms(0, 0, 0)))
+}
+
+fn row(entry: &HistoryEntry) -> [ColoredString; 5] {
+    let deployed = match (entry.deployed_at, entry.partially_applied_at) {
+        (Some(deployed_at), _) => format_ts(&deployed_at).color("blue"),
+        (None, Some(partially_applied_at)) => {
+            format!("partially applied {}", format_ts(&partially_applied_at)).color("red")
+        }
+        (None, None) => "pending".color("green"),
+    };
+
+    let fingerprint: String = entry
+        .seal_sign
+        .as_ref()
+        .map(|sign| {
+            sign.iter()
+                .take(FINGERPRINT_LEN)
+                .map(|byte| format!("{:02x}", byte))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    [
+        format!(" {}", entry.identity).color(""),
+        format_ts(&entry.registered_at).color(""),
+        deployed,
+        entry
+            .seal_algo
+            .clone()
+            .unwrap_or_default()
+            .color(""),
+        fingerprint.color("white"),
+    ]
+}
+
+fn format_ts(ts: &DateTime<FixedOffset>) -> String {
+    ts.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(
+            parse_since("2019-03-04T05:06:07+02:00").unwrap(),
+            FixedOffset::east(2 * 3600).ymd(2019, 3, 4).and_hms(5, 6, 7)
+        );
+        assert_eq!(
+            parse_since("2019-03-04T05:06:07.5Z").unwrap(),
+            FixedOffset::east(0).ymd(2019, 3, 4).and_hms_milli(5, 6, 7, 500)
+        );
+
+        // a date is midnight UTC, whatever the local timezone is
+        let since = parse_since("2019-03-04").unwrap();
+        assert_eq!(since, FixedOffset::east(0).ymd(2019, 3, 4).and_hms(0, 0, 0));
+        assert_eq!(since.offset(), &FixedOffset::east(0));
+
+        for value in &["", "yesterday", "2019-02-30", "2019-03-04 05:06:07", "04.03.2019"] {
+            assert!(parse_since(value).is_err(), "{:?} must be rejected", value);
+        }
+
+        assert!(parse_since("tomorrow")
+            .unwrap_err()
+            .to_string()
+            .contains("\"tomorrow\" is neither a date"));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::args::Args;
+use crate::grid::{Grid, Grid3};
+use crate::MainResult;
+use failure::Error;
+use path_abs::PathFile;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use warden_core::config::Config;
+use warden_core::dbms::capabilities::unsupported;
+use warden_core::dbms::Connection;
+use warden_core::migration::{self, identity::Identity, meta::Meta};
+
+pub fn run(args: &Args, to: &Option<String>, lock_timeout: Option<Duration>) -> MainResult {
+    let config = args.get_config()?;
+
+    let driver = config.driver.name();
+    let capabilities = config.driver.capabilities();
+
+    if !capabilities.revert {
+        Err(unsupported(driver, "revert", "the migrations cannot be rolled back"))?
+    }
+
+    if !capabilities.advisory_locking {
+        if lock_timeout.is_some() {
+            Err(unsupported(
+                driver,
+                "advisory locking",
+                "--lock-timeout and --no-wait are unavailable",
+            ))?
+        }
+
+        log::warn!(
+            "The \"{}\" driver does not support advisory locking, concurrent deployments are not prevented",
+            driver
+        );
+    }
+
+    super::confirm(args, &config, "Revert the migrations")?;
+
+    let conn = config.get_dbms_connection()?;
+
+    if !capabilities.advisory_locking {
+        return rollback(&config, &*conn, to);
+    }
+
+    // the rollback must not interleave with deployments
+    conn.lock(lock_timeout)?;
+
+    let result = rollback(&config, &*conn, to);
+    let unlocked = conn.unlock();
+
+    result?;
+    unlocked?;
+
+    Ok(())
+}
+
+fn rollback(config: &Config, conn: &Connection, to: &Option<String>) -> MainResult {
+    let last_deployed = match conn.get_last_deployed_migration()? {
+        Some(id) if id > 0 => id,
+        _ => Err(failure::err_msg("There are no deployed migrations to revert"))?,
+    };
+    let deployed = conn.get_deployed_migrations()?;
+
+    let ids: Vec<u128> = if let Some(pattern) = to {
+        let identity = migration::fs::lookup_pattern(&config.migrations, pattern).ok_or(
+            failure::err_msg(format!(
+                "could not determine the migration with pattern \"{}\"",
+                pattern
+            )),
+        )?;
+
+        let id = identity
+            .get_id()
+            .ok_or(failure::err_msg("could not decode migration id"))?;
+
+        if !deployed.contains(&id) {
+            Err(failure::err_msg(format!(
+                "Migration \"{}\" has not been deployed",
+                identity
+            )))?
+        }
+
+        deployed.range(id + 1..).cloned().collect()
+    } else {
+        vec![last_deployed]
+    };
+
+    let mut local = BTreeMap::new();
+
+    migration::fs::foreach_migration_sorted::<_, ()>(&config.migrations, |dir| {
+        let identity = Identity::from_str(dir.file_name().to_str()?)?;
+        local.insert(identity.get_id()?, identity);
+
+        None
+    });
+
+    // all of them are found before anything is reverted
+    let to_be_reverted = ids
+        .iter()
+        .map(|id| {
+            let identity = local.get(id).ok_or_else(|| {
+                failure::err_msg(format!(
+                    "Could not find the deployed migration \"{:0>6}\" in the repository",
+                    migration::base36::encode(*id)
+                ))
+            })?;
+
+            let meta = PathFile::new(config.migrations.join(format!("{}", identity)).join("meta.yml"))?;
+
+            Meta::open(meta)
+        })
+        .collect::<Result<Vec<Meta>, Error>>()?;
+
+    let mut grid: Grid3 = Grid::default();
+
+    for meta in to_be_reverted.into_iter().rev() {
+        let uid = &format!("{}", meta.get_identity());
+        let result = conn.revert(meta);
+        grid.row([" -", uid, if result.is_ok() { "[x]" } else { "[error!]" }]);
+
+        if result.is_err() {
+            print!("{}", grid.display());
+            result?;
+        }
+    }
+
+    print!("{}", grid.display());
+    Ok(())
+}
@@ -31,7 +31,19 @@ pub fn run(args: &Args) -> MainResult {
         relpath(&config.config_file)?.color(VAL_COLOUR),
     ]);
 
-    let connection = database(&mut grid, &config)?;
+    if let Some(ref env) = config.env {
+        let env = if config.require_confirmation {
+            format!("{} (requires confirmation)", env)
+        } else {
+            env.clone()
+        };
+
+        grid.row(["Environment".color(KEY_COLOUR), env.color(VAL_COLOUR)]);
+    }
+
+    driver(&mut grid, &config);
+
+    let connection = database(&mut grid, args, &config)?;
     crate::command::migration::list::migrations(&mut grid, &config, connection.as_ref())?;
 
     grid.row(["", ""]);
@@ -41,13 +53,45 @@ pub fn run(args: &Args) -> MainResult {
     Ok(())
 }
 
-fn database(grid: &mut Grid2, config: &Config) -> Result<Option<Box<Connection>>, Error> {
-    let database_url = if let Some(ref database_url) = config.database_url {
+fn driver(grid: &mut Grid2, config: &Config) {
+    grid.row([
+        "Driver".color(KEY_COLOUR),
+        config.driver.name().color(VAL_COLOUR),
+    ]);
+
+    let capabilities = config.driver.capabilities();
+    let list = capabilities.list();
+
+    let supported: Vec<&str> = list.iter().filter(|c| c.1).map(|c| c.0).collect();
+    let unsupported: Vec<&str> = list.iter().filter(|c| !c.1).map(|c| c.0).collect();
+
+    grid.row([
+        "Capabilities".color(KEY_COLOUR),
+        supported.join(", ").color(VAL_COLOUR),
+    ]);
+
+    if !unsupported.is_empty() {
+        grid.row([
+            "Unsupported".color(KEY_COLOUR),
+            unsupported.join(", ").color(ERR_COLOUR),
+        ]);
+    }
+}
+
+fn database(grid: &mut Grid2, args: &Args, config: &Config) -> Result<Option<Box<Connection>>, Error> {
+    let database = if let Some(ref database_url) = config.database_url {
         grid.row([
             "Database URL".color(KEY_COLOUR),
-            database_url.color(VAL_COLOUR),
+            args.redact(database_url).color(VAL_COLOUR),
+        ]);
+        database_url.clone()
+    } else if !config.connection_params.is_empty() {
+        let database = config.connection_params.to_string();
+        grid.row([
+            "Database".color(KEY_COLOUR),
+            database.color(VAL_COLOUR),
         ]);
-        database_url
+        database
     } else {
         grid.row([
             "Database URL".color(KEY_COLOUR),
@@ -56,13 +100,13 @@ fn database(grid: &mut Grid2, config: &Config) -> Result<Option<Box<Connection>>
         return Ok(None);
     };
 
-    log::trace!("Connecting to: {}", database_url);
+    log::trace!("Connecting to: {}", database);
 
     let conn = match config.get_dbms_connection() {
         Err(msg) => {
             grid.row([
                 "Connection error".color(ERR_COLOUR),
-                format!("{}", msg).color(ERR_COLOUR),
+                args.redact(&msg.to_string()).color(ERR_COLOUR),
             ]);
             return Ok(None);
         }
@@ -0,0 +1,37 @@
+use crate::args::Args;
+use crate::grid::{Grid, Grid3};
+use crate::MainResult;
+
+pub fn run(args: &Args) -> MainResult {
+    let config = args.get_config()?;
+
+    super::confirm(args, &config, "Upgrade the warden schema")?;
+
+    let conn = config.get_dbms_connection()?;
+    let locking = config.driver.capabilities().advisory_locking;
+
+    // the upgrades must not interleave with deployments
+    if locking {
+        conn.lock(None)?;
+    }
+
+    let applied = conn.upgrade()?;
+
+    if applied.is_empty() {
+        println!("The warden schema is up to date");
+    } else {
+        let mut grid: Grid3 = Grid::default();
+
+        for name in &applied {
+            grid.row([" -", name, "[x]"]);
+        }
+
+        print!("{}", grid.display());
+    }
+
+    if locking {
+        conn.unlock()?;
+    }
+
+    Ok(())
+}
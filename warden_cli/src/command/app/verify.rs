@@ -0,0 +1,145 @@
+use crate::args::Args;
+use crate::grid::{Grid, Grid3};
+use crate::migration::{self, MigrationStatus};
+use crate::MainResult;
+
+use colored::*;
+use failure::Error;
+use path_abs::PathFile;
+use std::collections::BTreeMap;
+
+use warden_core::dbms::Registration;
+
+pub fn run(args: &Args) -> MainResult {
+    let config = args.get_config()?;
+    let conn = config.get_dbms_connection()?;
+
+    let mut local: BTreeMap<u128, MigrationStatus> = BTreeMap::new();
+    for m in migration::read_migrations(&config) {
+        if let Some(id) = m.get_id().get_id() {
+            local.insert(id, m);
+        }
+    }
+
+    let registered = conn.get_registered_migrations()?;
+
+    let mut rows: BTreeMap<u128, (String, Option<&'static str>)> = BTreeMap::new();
+
+    for id in &registered {
+        let registration = conn
+            .get_registered_migration(*id)?
+            .ok_or(failure::err_msg("Could not fetch a registered migration"))?;
+
+        let drift = if let Some(m) = local.get(id) {
+            compare(m, &registration)?
+        } else {
+            Some("only in database")
+        };
+
+        rows.insert(*id, (format!("{}", registration.identity), drift));
+    }
+
+    for (id, m) in &local {
+        if registered.contains(id) || !m.is_sealed() {
+            continue;
+        }
+
+        rows.insert(*id, (format!("{}", m.get_id()), Some("not registered")));
+    }
+
+    let mut grid: Grid3 = Grid::default();
+    let mut drifted = 0;
+
+    for (_, (uid, drift)) in rows {
+        let status = if let Some(drift) = drift {
+            drifted += 1;
+            format!("[{}]", drift).color("red")
+        } else {
+            "[ok]".color("blue")
+        };
+
+        grid.row([" -".color(""), uid.color(""), status]);
+    }
+
+    print!("{}", grid.display());
+
+    if drifted > 0 {
+        Err(failure::err_msg(format!(
+            "{} migration(s) differ between the repository and the database",
+            drifted
+        )))?
+    }
+
+    Ok(())
+}
+
+/// Describe how the local migration differs from the registered one (if it does)
+fn compare(m: &MigrationStatus, registration: &Registration) -> Result<Option<&'static str>, Error> {
+    let meta = m.get_meta();
+
+    let source = if let Ok(target) = PathFile::new(meta.get_target()) {
+        target.read_string()?
+    } else {
+        return Ok(Some("not built"));
+    };
+
+    if source != registration.source {
+        return Ok(Some("source differs"));
+    }
+
+    if !m.is_sealed() {
+        return Ok(Some("not sealed"));
+    }
+
+    let seal = meta.get_seal_meta().read_the_seal()?;
+    let stored = &registration.seal;
+
+    if seal.algo.stringify() != stored.algo.stringify()
+        || seal.sign != stored.sign
+        || seal.revert_sign != stored.revert_sign
+        || seal.timestamp != stored.timestamp
+    {
+        return Ok(Some("seal differs"));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use path_abs::PathDir;
+
+    #[test]
+    fn test_compare() {
+        let migrations = testing::temp_dir("verify-compare");
+        let meta = testing::create_migration(&migrations, "000001--built", Some("create table test (id int);"));
+        testing::create_migration(&migrations, "000002--draft", None);
+
+        let status = |token: &str| MigrationStatus::from_dir(&PathDir::new(migrations.join(token)).unwrap()).unwrap();
+
+        let registration = Registration {
+            identity: meta.get_identity().clone(),
+            source: PathFile::new(meta.get_target()).unwrap().read_string().unwrap(),
+            seal: meta.get_seal_meta().read_the_seal().unwrap(),
+        };
+
+        let mut changed = registration.clone();
+        changed.source.push_str("\ndrop table test;");
+
+        let mut resealed = registration.clone();
+        resealed.seal.sign = vec![0; 4];
+
+        let built = status("000001--built");
+        assert_eq!(compare(&built, &registration).unwrap(), None);
+        assert_eq!(compare(&built, &changed).unwrap(), Some("source differs"));
+        assert_eq!(compare(&built, &resealed).unwrap(), Some("seal differs"));
+        assert_eq!(compare(&status("000002--draft"), &registration).unwrap(), Some("not built"));
+
+        PathFile::new(meta.get_seal_meta().get_file()).unwrap().remove().unwrap();
+        assert_eq!(compare(&status("000001--built"), &registration).unwrap(), Some("not sealed"));
+
+        migrations.remove_all().unwrap();
+    }
+}
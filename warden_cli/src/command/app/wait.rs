@@ -0,0 +1,126 @@
+use crate::args::Args;
+use crate::MainResult;
+
+use failure::Error;
+use log;
+use std::cmp;
+use std::iter;
+use std::thread;
+use std::time::{Duration, Instant};
+use warden_core::config::Config;
+use warden_core::dbms::Connection;
+
+/// How long to wait for the database unless told otherwise
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+const FIRST_DELAY: Duration = Duration::from_millis(250);
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+pub fn run(args: &Args, timeout: Duration) -> MainResult {
+    let config = args.get_config()?;
+
+    connect(&config, timeout)?;
+
+    println!("The database is reachable");
+
+    Ok(())
+}
+
+/// Open the connection, retrying with exponential backoff while the server is unreachable.
+/// The errors the driver does not consider transient (e.g. authentication) are returned right away
+pub fn connect(config: &Config, timeout: Duration) -> Result<Box<Connection>, Error> {
+    let started = Instant::now();
+    let mut delays = delays();
+    let mut attempt = 1;
+
+    loop {
+        log::info!("Connecting to the database (attempt {})", attempt);
+
+        let error = match config.get_dbms_connection() {
+            Ok(connection) => return Ok(connection),
+            Err(error) => error,
+        };
+
+        if !config.driver.is_transient_error(&error) {
+            return Err(error);
+        }
+
+        let elapsed = started.elapsed();
+
+        if elapsed >= timeout {
+            Err(failure::err_msg(format!(
+                "The database is still unreachable after {} attempts in {}s: {}",
+                attempt,
+                elapsed.as_secs(),
+                error
+            )))?
+        }
+
+        let delay = cmp::min(delays.next().unwrap_or(MAX_DELAY), timeout - elapsed);
+
+        log::info!("The database is unreachable ({}), retrying in {}ms", error, delay.as_millis());
+
+        thread::sleep(delay);
+
+        attempt += 1;
+    }
+}
+
+/// The delays between the attempts, doubling up to the maximum
+fn delays() -> impl Iterator<Item = Duration> {
+    iter::successors(Some(FIRST_DELAY), |delay| Some(cmp::min(*delay * 2, MAX_DELAY)))
+}
+
+/// `500ms`, `60s`, `2m`, `1h`, seconds if the unit is omitted
+pub fn parse_timeout(value: &str) -> Result<Duration, Error> {
+    let invalid = || {
+        failure::err_msg(format!(
+            "Invalid duration \"{}\", expected a number with the unit ms, s, m or h (e.g. 60s)",
+            value
+        ))
+    };
+
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+
+    let seconds = |multiplier: u64| {
+        number
+            .checked_mul(multiplier)
+            .map(Duration::from_secs)
+            .ok_or_else(invalid)
+    };
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => seconds(60),
+        "h" => seconds(3600),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_timeout("60s").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_timeout("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_timeout("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_timeout("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_timeout("0s").unwrap(), Duration::from_secs(0));
+
+        for invalid in &["", "s", "1.5s", "-1s", " 1s", "1 s", "1d", "1S", "99999999999999999999", "9999999999999999999h"] {
+            assert!(parse_timeout(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_delays() {
+        let delays: Vec<u128> = delays().take(8).map(|delay| delay.as_millis()).collect();
+        assert_eq!(delays, [250, 500, 1000, 2000, 4000, 8000, 10000, 10000]);
+    }
+}
@@ -29,6 +29,8 @@ impl Command {
             None
         }).ok();
 
+        println!(" - external:<command> (see warden_core::dbms::external for the protocol)");
+
         Ok(())
     }
 }
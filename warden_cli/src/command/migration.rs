@@ -1,11 +1,13 @@
 mod build;
 mod create;
 pub mod list;
+mod restore;
 mod seal;
 
 use crate::args::Args;
 use crate::command;
 use crate::MainResult;
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -52,6 +54,21 @@ pub enum Command {
         )]
         skip_rebuild: bool,
         pattern: Option<String>,
+    },
+
+    #[structopt(
+        name = "restore",
+        about = "Restore a migration folder from the snapshot stored in the database",
+        raw(setting = "structopt::clap::AppSettings::ColoredHelp")
+    )]
+    Restore {
+        #[structopt(
+            long = "into",
+            help = "Folder to restore the migration into (the migrations folder by default)",
+            parse(from_os_str)
+        )]
+        into: Option<PathBuf>,
+        pattern: String,
     }, // #[structopt(name = "")]
 }
 
@@ -65,6 +82,7 @@ impl Command {
                 skip_rebuild,
                 pattern,
             } => seal::run(args, pattern, *skip_rebuild),
+            Command::Restore { into, pattern } => restore::run(args, pattern, into),
         }
     }
 }
@@ -24,7 +24,18 @@ pub fn run(args: &Args, pattern: &Option<String>, force: bool) -> MainResult {
 }
 
 pub fn build(meta: Meta, target: PathAbs) -> MainResult {
-    let sewer = Sewer::new(meta)?;
+    if let Some(revert) = meta.get_revert() {
+        let revert_target = revert.get_target().absolute()?;
+
+        if let Some(sewer) = Sewer::revert(meta.clone())? {
+            sew(sewer, revert_target)?;
+        }
+    }
+
+    sew(Sewer::new(meta)?, target)
+}
+
+fn sew(sewer: Sewer, target: PathAbs) -> MainResult {
     let graph = sewer.sew_up()?;
     let migration = sewer.sewage(&graph)?;
 
@@ -4,10 +4,13 @@ use failure::Error;
 use path_abs::{PathDir, PathFile};
 use warden_core::config::Config;
 use warden_core::migration::algo::Algo;
+use warden_core::migration::execution::ExecutionMode;
 use warden_core::migration::identity::Identity;
 use warden_core::migration::meta::Meta;
 use warden_core::migration::path::FileOrDir;
+use warden_core::migration::revert::RevertMeta;
 use warden_core::migration::seal::SealMeta;
+use warden_core::migration::transaction::TransactionMode;
 
 pub fn run(args: &Args, name: &str) -> MainResult {
     let config = args.get_config()?;
@@ -26,6 +29,11 @@ fn create(config: &Config, id: &Identity) -> Result<Meta, Error> {
     let meta = PathFile::create(root.join("meta.yml"))?;
     let source = FileOrDir::from(PathDir::create(root.join("sql"))?);
     let target = root.join("migration.sql");
+    let revert = RevertMeta::build(
+        FileOrDir::from(PathDir::create(root.join("revert"))?),
+        // not "revert.sql", which would be the parent package of the patches in revert/
+        root.join("revert-migration.sql"),
+    );
     let seal_meta = SealMeta::build(root.join("seal.yml"), Algo::default());
 
     let meta = Meta::create(
@@ -35,6 +43,11 @@ fn create(config: &Config, id: &Identity) -> Result<Meta, Error> {
         seal_meta,
         source,
         target,
+        Some(revert),
+        TransactionMode::default(),
+        ExecutionMode::default(),
+        None,
+        None,
     )?;
 
     Ok(meta)
@@ -3,6 +3,7 @@ use crate::grid::{Grid, Grid2};
 use crate::migration;
 use crate::MainResult;
 use colored::*;
+use std::collections::BTreeSet;
 use warden_core::config::Config;
 use warden_core::dbms::Connection;
 
@@ -23,24 +24,25 @@ pub fn run(args: &Args) -> MainResult {
 pub fn migrations(grid: &mut Grid2, config: &Config, conn: Option<&Box<Connection>>) -> MainResult {
     let migrations = migration::read_migrations(config);
 
-    let last_deployed = if let Some(ref conn) = conn {
-        conn.get_last_deployed_migration()?
+    let (last_deployed, deployed) = if let Some(ref conn) = conn {
+        (
+            conn.get_last_deployed_migration()?,
+            conn.get_deployed_migrations()?,
+        )
     } else {
-        None
+        (None, BTreeSet::new())
     };
 
+    let mut out_of_order = 0;
+
     grid.row(["", ""]);
     grid.row(["Migrations:".color("cyan").bold(), "".color("white")]);
     grid.row(["", ""]);
 
     for m in migrations {
         let is_deployed: Option<bool> = if conn.is_some() {
-            Some(if let Some(last_deployed) = last_deployed {
-                if let Some(id) = m.get_id().get_id() {
-                    last_deployed >= id
-                } else {
-                    false
-                }
+            Some(if let Some(id) = m.get_id().get_id() {
+                deployed.contains(&id)
             } else {
                 false
             })
@@ -48,6 +50,18 @@ pub fn migrations(grid: &mut Grid2, config: &Config, conn: Option<&Box<Connectio
             None
         };
 
+        // sealed, but skipped while the later ones have been deployed
+        let is_out_of_order = m.is_sealed()
+            && is_deployed == Some(false)
+            && match (last_deployed, m.get_id().get_id()) {
+                (Some(last_deployed), Some(id)) => id < last_deployed,
+                _ => false,
+            };
+
+        if is_out_of_order {
+            out_of_order += 1;
+        }
+
         let seal = format!(
             "{}",
             if m.is_sealed() {
@@ -63,6 +77,8 @@ pub fn migrations(grid: &mut Grid2, config: &Config, conn: Option<&Box<Connectio
             } else {
                 "green"
             }
+        } else if is_out_of_order {
+            "yellow"
         } else if let Some(deployed) = is_deployed {
             if deployed {
                 "blue"
@@ -85,6 +101,8 @@ pub fn migrations(grid: &mut Grid2, config: &Config, conn: Option<&Box<Connectio
                 } else {
                     "".color("white")
                 }
+            } else if is_out_of_order {
+                "[!]".color("yellow")
             } else if let Some(deployed) = is_deployed {
                 if deployed {
                     "[+]".color("blue")
@@ -99,5 +117,17 @@ pub fn migrations(grid: &mut Grid2, config: &Config, conn: Option<&Box<Connectio
         grid.row([format!(" {}{}", seal, name), format!("{}", status)]);
     }
 
+    if out_of_order > 0 {
+        grid.row(["", ""]);
+        grid.row([
+            " [!]".color("yellow"),
+            format!(
+                "{} migration(s) older than the last deployed one, see `app deploy --allow-out-of-order`",
+                out_of_order
+            )
+            .color("yellow"),
+        ]);
+    }
+
     Ok(())
 }
@@ -0,0 +1,196 @@
+use crate::args::Args;
+use crate::MainResult;
+use failure::Error;
+use path_abs::{PathArc, PathDir, PathFile};
+use std::path::PathBuf;
+use warden_core::config::Config;
+use warden_core::dbms::capabilities::unsupported;
+use warden_core::dbms::{Connection, Registration};
+use warden_core::migration::snapshot::Snapshot;
+use warden_core::migration::{self, identity::Identity, meta::Meta};
+use warden_core::path::relpath;
+
+pub fn run(args: &Args, pattern: &str, into: &Option<PathBuf>) -> MainResult {
+    let config = args.get_config()?;
+
+    if !config.driver.capabilities().snapshot_storage {
+        Err(unsupported(
+            config.driver.name(),
+            "snapshot storage",
+            "the migrations cannot be restored",
+        ))?
+    }
+
+    let conn = config.get_dbms_connection()?;
+
+    let id = resolve(&config, &*conn, pattern)?;
+
+    let registration = conn
+        .get_registered_migration(id)?
+        .ok_or(failure::err_msg(format!(
+            "Migration \"{}\" has not been registered",
+            migration::base36::encode(id)
+        )))?;
+
+    let snapshot = conn
+        .get_migration_snapshot(id)?
+        .ok_or(failure::err_msg(format!(
+            "There is no snapshot of the migration \"{}\"",
+            registration.identity
+        )))?;
+
+    let into = if let Some(into) = into {
+        PathDir::create_all(PathArc::new(into).absolute()?)?
+    } else {
+        config.migrations.clone()
+    };
+
+    let folder = restore(&snapshot, &into, &registration)?;
+
+    println!(
+        "Restored migration {} into {:?}",
+        registration.identity,
+        relpath(&folder)?
+    );
+
+    Ok(())
+}
+
+/// Unpack the snapshot into the folder and verify it against the registration,
+/// leaving nothing behind if it does not match.
+/// Returns the restored migration folder
+fn restore(snapshot: &Snapshot, into: &PathDir, registration: &Registration) -> Result<PathDir, Error> {
+    let folder = into.join(format!("{}", registration.identity));
+
+    if folder.exists() {
+        Err(failure::err_msg(format!(
+            "{:?} already exists",
+            relpath(&folder)?
+        )))?
+    }
+
+    snapshot.unpack(into)?;
+
+    let folder = PathDir::new(folder)?;
+
+    if let Err(error) = verify(&folder, registration) {
+        folder.clone().remove_all()?;
+        Err(error)?
+    }
+
+    Ok(folder)
+}
+
+/// Find the migration ID by the pattern, looking up the repository first
+/// and then the migrations registered in the database
+fn resolve(config: &Config, conn: &Connection, pattern: &str) -> Result<u128, Error> {
+    if let Some(id) = migration::fs::lookup_pattern(&config.migrations, pattern)
+        .and_then(|identity| identity.get_id())
+    {
+        return Ok(id);
+    }
+
+    let registered = conn.get_registered_migrations()?;
+
+    let id = Identity::from_str(pattern)
+        .and_then(|identity| identity.get_id())
+        .or_else(|| migration::base36::decode(pattern));
+
+    if let Some(id) = id {
+        if registered.contains(&id) {
+            return Ok(id);
+        }
+    }
+
+    let mut result = None;
+
+    for id in registered {
+        let name_matches = conn
+            .get_registered_migration(id)?
+            .map(|registration| registration.identity.get_name() == pattern)
+            .unwrap_or(false);
+
+        if name_matches {
+            if result.is_some() {
+                Err(failure::err_msg(format!(
+                    "More than one migration matches the pattern \"{}\"",
+                    pattern
+                )))?
+            }
+
+            result = Some(id);
+        }
+    }
+
+    result.ok_or(failure::err_msg(format!(
+        "could not determine the migration with pattern \"{}\"",
+        pattern
+    )))
+}
+
+/// Check the restored migration against the seal stored in the database
+fn verify(folder: &PathDir, registration: &Registration) -> Result<(), Error> {
+    let meta = Meta::open(PathFile::new(folder.join("meta.yml"))?)?;
+
+    let target = PathFile::new(meta.get_target())?;
+
+    if !registration.seal.verify(target.read_string()?.as_bytes()) {
+        Err(failure::err_msg(format!(
+            "The restored {:?} does not match the seal stored in the database",
+            relpath(&target)?
+        )))?
+    }
+
+    if let (Some(revert), Some(_)) = (meta.get_revert(), &registration.seal.revert_sign) {
+        let target = PathFile::new(revert.get_target())?;
+
+        if !registration.seal.verify_revert(target.read_string()?.as_bytes()) {
+            Err(failure::err_msg(format!(
+                "The restored {:?} does not match the seal stored in the database",
+                relpath(&target)?
+            )))?
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use warden_core::migration::snapshot::Format;
+
+    #[test]
+    fn test_restore() {
+        let migrations = testing::temp_dir("restore-source");
+        let into = testing::temp_dir("restore-into");
+
+        let meta = testing::create_migration(&migrations, "000001--first", Some("create table test (id int);"));
+        let snapshot = Snapshot::take(Format::TarGz, &meta).unwrap();
+
+        let registration = Registration {
+            identity: meta.get_identity().clone(),
+            source: PathFile::new(meta.get_target()).unwrap().read_string().unwrap(),
+            seal: meta.get_seal_meta().read_the_seal().unwrap(),
+        };
+
+        let mut resealed = registration.clone();
+        resealed.seal.sign = vec![0; 4];
+
+        // a mismatch leaves nothing behind
+        assert!(restore(&snapshot, &into, &resealed).is_err());
+        assert!(!into.join("000001--first").exists());
+
+        let folder = restore(&snapshot, &into, &registration).unwrap();
+        assert!(folder.join("meta.yml").exists());
+        assert!(folder.join("migration.sql").exists());
+
+        // never overwrites an existing migration
+        assert!(restore(&snapshot, &into, &registration).is_err());
+        assert!(folder.join("meta.yml").exists());
+
+        migrations.remove_all().unwrap();
+        into.remove_all().unwrap();
+    }
+}
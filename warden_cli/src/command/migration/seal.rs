@@ -22,8 +22,14 @@ pub fn run(args: &Args, pattern: &Option<String>, mut skip_rebuild: bool) -> Mai
 
     let target = meta.get_target().absolute()?;
 
+    let revert_built = if let Some(revert) = meta.get_revert() {
+        PathFile::new(revert.get_target()).is_ok()
+    } else {
+        true
+    };
+
     if skip_rebuild {
-        if let Ok(_) = PathFile::new(&target) {
+        if let (Ok(_), true) = (PathFile::new(&target), revert_built) {
             log::warn!("Migration exists, skipping rebuild");
         } else {
             skip_rebuild = false;
@@ -36,7 +42,16 @@ pub fn run(args: &Args, pattern: &Option<String>, mut skip_rebuild: bool) -> Mai
         // PathFile::create(&target)?.write_str(&builder.generate_migration()?)?;
     }
 
-    let migration = PathFile::new(&target)?;
+    let migration = PathFile::new(&target)?.read_string()?;
+
+    let revert = if let Some(revert) = meta.get_revert() {
+        Some(PathFile::new(revert.get_target())?.read_string()?)
+    } else {
+        None
+    };
 
-    Ok(seal_meta.make(&migration.read_string()?.as_bytes())?)
+    Ok(seal_meta.make(
+        migration.as_bytes(),
+        revert.as_ref().map(|revert| revert.as_bytes()),
+    )?)
 }
@@ -3,10 +3,16 @@ mod command;
 pub mod grid;
 mod migration;
 
+#[cfg(test)]
+mod testing;
+
 use dotenv::dotenv;
 use env_logger;
 use exitfailure::ExitFailure;
+use std::io::Write;
+use std::process;
 use structopt::StructOpt;
+use warden_core::config::redact::redact;
 
 use args::Args;
 use command::Command;
@@ -21,12 +27,42 @@ fn main() -> MainResult {
         warden_postgres::driver::register_driver();
     }
 
+    #[cfg(feature = "with-mock")]
+    {
+        warden_mock::driver::register_driver();
+    }
+
+    #[cfg(feature = "with-mysql")]
+    {
+        warden_mysql::driver::register_driver();
+    }
+
+    #[cfg(feature = "with-sqlite")]
+    {
+        warden_sqlite::driver::register_driver();
+    }
+
     let args = Args::from_args();
 
-    env_logger::builder()
+    let mut logger = env_logger::builder();
+
+    logger
         .default_format_timestamp(false)
-        .filter_level(args.get_log_level_filter())
-        .init();
+        .filter_level(args.get_log_level_filter());
+
+    if !args.show_secrets {
+        logger.format(|buf, record| {
+            writeln!(
+                buf,
+                "[{:<5} {}] {}",
+                buf.default_styled_level(record.level()),
+                record.module_path().unwrap_or(""),
+                redact(&record.args().to_string())
+            )
+        });
+    }
+
+    logger.init();
 
     log::set_max_level(args.get_log_level_filter());
 
@@ -48,5 +84,15 @@ fn main() -> MainResult {
         log::info!("No config defined");
     }
 
-    Command::run(args)
+    if args.show_secrets {
+        return Command::run(args);
+    }
+
+    // printed the way returning the error would, but with the passwords hidden
+    if let Err(error) = Command::run(args) {
+        eprintln!("Error: {}", redact(&format!("{:?}", error)));
+        process::exit(1);
+    }
+
+    Ok(())
 }
@@ -16,6 +16,10 @@ impl MigrationStatus {
         self.meta.get_identity()
     }
 
+    pub fn get_meta(&self) -> &Meta {
+        &self.meta
+    }
+
     pub fn is_sealed(&self) -> bool {
         self.sealed
     }
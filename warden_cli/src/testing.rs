@@ -0,0 +1,78 @@
+//! Helpers of the tests, making the migration folders the way the commands do
+
+pub use warden_core::testing::{create_migration, create_migration_with_revert, temp_dir};
+
+use failure::Error;
+use path_abs::{PathDir, PathFile};
+use std::sync::Once;
+use warden_core::dbms::driver::{Driver, DriverFactory};
+use warden_core::dbms::{Capabilities, Connection};
+use warden_mock::driver::Mock;
+
+/// The mock driver supporting nothing, so that the modes depending on the capabilities are refused
+struct Incapable;
+
+static INCAPABLE: &str = "mock-incapable";
+
+impl Driver for Incapable {
+    fn name(&self) -> &'static str {
+        INCAPABLE
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::none()
+    }
+
+    fn create_initial_migration(&self, folder: &PathDir) -> Result<(), Error> {
+        Mock.create_initial_migration(folder)
+    }
+
+    fn open_connection(&self, url: &str, registry_schema: &str) -> Result<Box<Connection>, Error> {
+        Mock.open_connection(url, registry_schema)
+    }
+}
+
+struct IncapableFactory;
+
+impl DriverFactory for IncapableFactory {
+    fn name(&self) -> &'static str {
+        INCAPABLE
+    }
+
+    fn new(&self) -> Box<Driver> {
+        Box::new(Incapable)
+    }
+}
+
+/// The project of the mock database (`mock://<database>`), with the initial migration only
+pub fn mock_project(name: &str, database: &str) -> PathDir {
+    project(name, "mock", database)
+}
+
+/// The project of the mock database, driven by the mock driver supporting nothing
+pub fn incapable_mock_project(name: &str, database: &str) -> PathDir {
+    project(name, INCAPABLE, database)
+}
+
+fn project(name: &str, driver: &str, database: &str) -> PathDir {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        warden_mock::driver::register_driver();
+        warden_core::dbms::driver::register_driver(Box::new(IncapableFactory));
+    });
+
+    let root = temp_dir(name);
+    let migrations = PathDir::create(root.join("migrations")).unwrap();
+
+    Mock.create_initial_migration(&migrations).unwrap();
+
+    PathFile::create(PathDir::create(root.join(".warden")).unwrap().join("config.yml"))
+        .unwrap()
+        .write_str(&format!(
+            "version: 0.2\n...\nconnection: 'mock://{}'\nrepository: '..'\nmigrations: '../migrations'\ndriver: '{}'\n",
+            database, driver
+        ))
+        .unwrap();
+
+    root
+}
@@ -1,6 +1,7 @@
 pub mod generator;
+pub mod redact;
 
-use crate::dbms::{self, Connection, Driver};
+use crate::dbms::{self, Connection, ConnectionParams, Driver, Timeouts, Tls};
 use crate::path;
 
 use failure::{self, Error};
@@ -12,34 +13,52 @@ use yamlette::yamlette;
 use yamlette::model::Fraction;
 use yamlette::model::schema::yamlette::Yamlette;
 
+/// The schema (or the namespace) of the warden bookkeeping data by default
+pub const DEFAULT_REGISTRY_SCHEMA: &str = "warden";
 
 pub struct Config {
     pub config_file: PathFile,
     pub database_url: Option<String>,
+
+    /// The connection settings given field by field (if no URL given)
+    pub connection_params: ConnectionParams,
+
     pub repository: PathDir,
     pub migrations: PathDir,
-    pub driver: Box<Driver>
+    pub driver: Box<Driver>,
+    pub registry_schema: String,
+    pub timeouts: Timeouts,
+    pub tls: Tls,
+
+    /// The environment the settings have been taken for
+    pub env: Option<String>,
+
+    /// Whether to ask before changing the database
+    pub require_confirmation: bool
 }
 
 impl Config {
-    pub fn new(path: &Option<PathArc>) -> Result<Config, Error> {
+    pub fn new(path: &Option<PathArc>, env: &Option<String>) -> Result<Config, Error> {
         let file = Self::find_config_file(path, PathDir::current_dir().ok())?;
-        Self::open(file)
+        Self::open_env(file, env)
     }
 
     pub fn get_dbms_connection(&self) -> Result<Box<Connection>, Error> {
-        let db_url = if let Some(ref db_url) = self.database_url {
-            db_url
-        } else {
+        if self.database_url.is_none() && self.connection_params.is_empty() {
             return Err(failure::err_msg(
                 format!(
                     "Database connection URL is undefined (in {})",
                     path::relpath(&self.config_file)?
                 )
             ))
-        };
+        }
 
-        self.driver.open_connection(db_url)
+        self.driver.open_configured_connection(
+            self.database_url.as_deref(),
+            &self.connection_params,
+            &self.tls,
+            &self.registry_schema
+        )
     }
 
     // pub fn at_dir(args: &Args, cur_dir: PathDir) -> Result<Config, Error> {
@@ -47,9 +66,131 @@ impl Config {
     //     Self::open(file)
     // }
 
-    pub fn open(file: PathFile) -> Result<Config, Error> { Self::parse_config(file) }
+    pub fn open(file: PathFile) -> Result<Config, Error> { Self::open_env(file, &None) }
+
+    /// Open the config for the environment (WARDEN_ENV by default)
+    pub fn open_env(file: PathFile, env: &Option<String>) -> Result<Config, Error> {
+        Self::parse_config(file, env.clone(), env::var("WARDEN_ENV").ok())
+    }
+
+    /// Only the environment selected explicitly is refused, WARDEN_ENV may be meant for other projects
+    fn parse_config_v_0_1(file: PathFile, env: Option<String>, env_var: Option<String>) -> Result<Config, Error> {
+        if let Some(env) = env {
+            Err(failure::err_msg(format!(
+                "Environment \"{}\" is selected, but the config version 0.1 has no environments",
+                env
+            )))?
+        }
+
+        if let Some(env) = env_var {
+            log::warn!(
+                "WARDEN_ENV=\"{}\" is ignored, the config version 0.1 has no environments",
+                env
+            );
+        }
+
+        let mut config = Self::parse_settings(file, None)?;
+
+        // TLS is off by default, as it has been before the config version 0.2
+        if config.tls.mode.is_none() {
+            config.tls.mode = Some("disable".to_string());
+        }
+
+        Ok(config)
+    }
+
+    fn parse_config_v_0_2(file: PathFile, env: Option<String>, env_var: Option<String>) -> Result<Config, Error> {
+        let env = Self::select_env(&file, env.or(env_var))?;
+
+        if let Some(ref env) = env {
+            // the env tags are resolved while reading, so the variables must be there beforehand
+            Self::load_dotenv(&file, env)?;
+        }
+
+        Self::parse_settings(file, env)
+    }
+
+    /// The environment to use, the default one unless selected
+    fn select_env(file: &PathFile, env: Option<String>) -> Result<Option<String>, Error> {
+        let mut file_read = file.read()?;
+        let schema = Yamlette::new();
+        let mut names: Vec<String> = Vec::new();
+
+        yamlette!(
+            read ;
+            &mut file_read ;
+            [[], [{
+                "default_env" => (default_env:String),
+                "environments" => (call &mut |environments| {
+                    let mut key = environments.into_map();
+
+                    while let Some(ptr) = key {
+                        if let Some(name) = ptr.into::<String>() {
+                            names.push(name);
+                        }
+
+                        key = ptr.next_sibling().and_then(|value| value.next_sibling());
+                    }
+                })
+            }]] ;
+            { schema: schema }
+        );
+
+        let env = env.or(default_env);
+
+        match env {
+            Some(env) => {
+                if names.contains(&env) {
+                    Ok(Some(env))
+                } else {
+                    Err(failure::err_msg(format!(
+                        "Unknown environment \"{}\" (defined: {})",
+                        env,
+                        names.join(", ")
+                    )))
+                }
+            }
+            None if names.is_empty() => Ok(None),
+            None => Err(failure::err_msg(format!(
+                "The environment is not selected, use --env or WARDEN_ENV (defined: {})",
+                names.join(", ")
+            ))),
+        }
+    }
+
+    fn load_dotenv(file: &PathFile, env: &str) -> Result<(), Error> {
+        let mut file_read = file.read()?;
+        let schema = Yamlette::new();
+
+        yamlette!(
+            read ;
+            &mut file_read ;
+            [[], [{
+                "environments" => {
+                    env => {
+                        "dotenv" => (dotenv:String)
+                    }
+                }
+            }]] ;
+            { schema: schema }
+        );
+
+        if let Some(dotenv) = dotenv {
+            let path = Self::get_folder(file)?.join(dotenv);
+            log::info!("Dotenv: {:?}", path);
+
+            dotenv::from_path(&path).map_err(|error| {
+                failure::err_msg(format!("Could not load the dotenv file {:?}: {}", path, error))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// The settings of the environment override the common ones
+    fn parse_settings(file: PathFile, env: Option<String>) -> Result<Config, Error> {
+        let env_name = env.as_deref().unwrap_or("");
 
-    fn parse_config_v_0_1(file: PathFile) -> Result<Config, Error> {
         let mut file_read = file.read()?;
         let schema = Yamlette::new();
         yamlette!(
@@ -57,14 +198,77 @@ impl Config {
             &mut file_read ;
             [[], [{
                 "connection" => (connection_url:String),
+                "connection" => {
+                    "host" => (host:String),
+                    "port" => (port:u16),
+                    "user" => (user:String),
+                    "dbname" => (dbname:String),
+                    "password_file" => (password_file:String),
+                    "passfile" => (passfile:String)
+                },
                 "repository" => (repo_relpath:String),
                 "migrations" => (migrations:String),
-                "driver" => (driver:String)
+                "driver" => (driver:String),
+                "registry_schema" => (registry_schema:String),
+                "lock_timeout" => (lock_timeout:String),
+                "statement_timeout" => (statement_timeout:String),
+                "lock_retries" => (lock_retries:u32),
+                "sslmode" => (sslmode:String),
+                "sslrootcert" => (sslrootcert:String),
+                "sslcert" => (sslcert:String),
+                "sslkey" => (sslkey:String),
+                "require_confirmation" => (require_confirmation:bool),
+                "environments" => {
+                    env_name => {
+                        "connection" => (env_connection_url:String),
+                        "connection" => {
+                            "host" => (env_host:String),
+                            "port" => (env_port:u16),
+                            "user" => (env_user:String),
+                            "dbname" => (env_dbname:String),
+                            "password_file" => (env_password_file:String),
+                            "passfile" => (env_passfile:String)
+                        },
+                        "lock_timeout" => (env_lock_timeout:String),
+                        "statement_timeout" => (env_statement_timeout:String),
+                        "lock_retries" => (env_lock_retries:u32),
+                        "sslmode" => (env_sslmode:String),
+                        "sslrootcert" => (env_sslrootcert:String),
+                        "sslcert" => (env_sslcert:String),
+                        "sslkey" => (env_sslkey:String),
+                        "require_confirmation" => (env_require_confirmation:bool)
+                    }
+                }
             }]] ;
             { schema: schema }
         );
 
-        let database_url = connection_url;
+        let cfg_folder = Self::get_folder(&file)?;
+
+        let params = ConnectionParams {
+            host: host,
+            port: port,
+            user: user,
+            dbname: dbname,
+            password_file: password_file.map(|path| cfg_folder.join(path)),
+            passfile: passfile.map(|path| cfg_folder.join(path))
+        };
+
+        let env_params = ConnectionParams {
+            host: env_host,
+            port: env_port,
+            user: env_user,
+            dbname: env_dbname,
+            password_file: env_password_file.map(|path| cfg_folder.join(path)),
+            passfile: env_passfile.map(|path| cfg_folder.join(path))
+        };
+
+        // the connection of the environment replaces the common one as a whole
+        let (database_url, connection_params) = if env_connection_url.is_some() || !env_params.is_empty() {
+            (env_connection_url, env_params)
+        } else {
+            (connection_url, params)
+        };
 
         let driver = if let Some(driver) = driver {
             if let Some(driver) = dbms::driver::lookup(&driver) {
@@ -76,12 +280,6 @@ impl Config {
             Err(failure::err_msg("driver is not defined"))?
         };
 
-        let cfg_folder = if let Some(p) = file.parent_dir() {
-            p
-        } else {
-            Err(failure::err_msg("could not identify configuration folder"))?
-        };
-
         let repository = if let Some(path) = repo_relpath {
             cfg_folder.join(path).canonicalize()?.into_dir()?
         } else {
@@ -98,12 +296,33 @@ impl Config {
             config_file: file,
             repository: repository,
             database_url: database_url,
+            connection_params: connection_params,
             migrations: migrations,
-            driver: driver
+            driver: driver,
+            registry_schema: registry_schema.unwrap_or_else(|| DEFAULT_REGISTRY_SCHEMA.to_string()),
+            timeouts: Timeouts {
+                lock_timeout: env_lock_timeout.or(lock_timeout),
+                statement_timeout: env_statement_timeout.or(statement_timeout),
+                lock_retries: env_lock_retries.or(lock_retries).unwrap_or(0)
+            },
+            tls: Tls {
+                mode: env_sslmode.or(sslmode),
+                root_cert: env_sslrootcert.or(sslrootcert).map(|path| cfg_folder.join(path)),
+                cert: env_sslcert.or(sslcert).map(|path| cfg_folder.join(path)),
+                key: env_sslkey.or(sslkey).map(|path| cfg_folder.join(path))
+            },
+            env: env,
+            require_confirmation: env_require_confirmation.or(require_confirmation).unwrap_or(false)
         })
     }
 
-    fn parse_config(file: PathFile) -> Result<Config, Error> {
+    fn get_folder(file: &PathFile) -> Result<PathDir, Error> {
+        file.parent_dir()
+            .ok_or_else(|| failure::err_msg("could not identify configuration folder"))
+    }
+
+    /// The environment is either selected explicitly or given in WARDEN_ENV (`env_var`)
+    fn parse_config(file: PathFile, env: Option<String>, env_var: Option<String>) -> Result<Config, Error> {
         let mut file_read = file.read()?;
         let schema = Yamlette::new();
 
@@ -118,7 +337,9 @@ impl Config {
 
         if let Some(version) = version {
             if version == Fraction::new(1u8, 10u8) {
-                Self::parse_config_v_0_1(file)
+                Self::parse_config_v_0_1(file, env, env_var)
+            } else if version == Fraction::new(2u8, 10u8) {
+                Self::parse_config_v_0_2(file, env, env_var)
             } else {
                 log::error!("Unsupported config version {:.8}", version);
                 Err(failure::err_msg("Unsupported version"))?
@@ -153,3 +374,142 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// The config file along with the folders it points to
+    fn write_config(name: &str, content: &str) -> PathFile {
+        let root = testing::temp_dir(&format!("config-{}", name));
+        PathDir::create(root.join("migrations")).unwrap();
+
+        let file = PathFile::create(root.join("config.yml")).unwrap();
+        file.write_str(content).unwrap();
+        file
+    }
+
+    const SETTINGS: &str = "repository: '.'\nmigrations: 'migrations'\ndriver: 'external:true'\n";
+
+    #[test]
+    fn test_tls_default() {
+        let file = write_config("tls-0-1", &format!("version: 0.1\n...\n{}", SETTINGS));
+        assert_eq!(Config::parse_config(file.clone(), None, None).unwrap().tls.mode.as_deref(), Some("disable"));
+
+        file.append_str("sslmode: 'require'\n").unwrap();
+        assert_eq!(Config::parse_config(file, None, None).unwrap().tls.mode.as_deref(), Some("require"));
+
+        // the drivers without TLS support connect with it disabled
+        let (command, helper) = testing::external_helper(
+            "config-tls-0-1",
+            &[("open", r#"{"result": null}"#), ("catalog", r#"{"result": "app"}"#)],
+        );
+        let settings = format!(
+            "version: 0.1\n...\nconnection: 'app'\nrepository: '.'\nmigrations: 'migrations'\ndriver: 'external:{}'\n",
+            command
+        );
+
+        let file = write_config("tls-0-1-connect", &settings);
+        let connection = Config::parse_config(file.clone(), None, None).unwrap().get_dbms_connection().unwrap();
+        assert_eq!(connection.get_catalog(), "app");
+
+        file.append_str("sslmode: 'require'\n").unwrap();
+        assert!(Config::parse_config(file, None, None).unwrap().get_dbms_connection().is_err());
+
+        helper.remove_all().unwrap();
+
+        // prefer, left to the driver
+        let file = write_config("tls-0-2", &format!("version: 0.2\n...\n{}", SETTINGS));
+        assert_eq!(Config::parse_config(file, None, None).unwrap().tls.mode, None);
+    }
+
+    #[test]
+    fn test_env_v_0_1() {
+        let file = write_config("env-0-1", &format!("version: 0.1\n...\n{}", SETTINGS));
+
+        assert!(Config::parse_config(file.clone(), Some("prod".to_string()), None).is_err());
+        assert!(Config::parse_config(file.clone(), Some("prod".to_string()), Some("prod".to_string())).is_err());
+
+        let config = Config::parse_config(file, None, Some("prod".to_string())).unwrap();
+        assert_eq!(config.env, None);
+    }
+
+    #[test]
+    fn test_select_env() {
+        let some = |env: &str| Some(env.to_string());
+
+        let file = write_config(
+            "select-env",
+            &format!("version: 0.2\n...\n{}default_env: 'dev'\nenvironments:\n  dev: {{}}\n  prod: {{}}\n", SETTINGS),
+        );
+
+        assert_eq!(Config::select_env(&file, None).unwrap(), some("dev"));
+        assert_eq!(Config::select_env(&file, some("prod")).unwrap(), some("prod"));
+        assert!(Config::select_env(&file, some("test")).is_err());
+
+        // the one selected explicitly takes precedence over WARDEN_ENV
+        assert_eq!(Config::parse_config(file.clone(), some("prod"), some("dev")).unwrap().env, some("prod"));
+        assert_eq!(Config::parse_config(file, None, some("prod")).unwrap().env, some("prod"));
+
+        let file = write_config(
+            "select-env-no-default",
+            &format!("version: 0.2\n...\n{}environments:\n  dev: {{}}\n", SETTINGS),
+        );
+
+        assert!(Config::select_env(&file, None).is_err());
+        assert_eq!(Config::select_env(&file, some("dev")).unwrap(), some("dev"));
+
+        let file = write_config("select-env-none", &format!("version: 0.2\n...\n{}", SETTINGS));
+
+        assert_eq!(Config::select_env(&file, None).unwrap(), None);
+        assert!(Config::select_env(&file, some("dev")).is_err());
+    }
+
+    const ENVIRONMENTS: &str = "\
+connection: 'postgres://localhost/app'
+lock_timeout: '5s'
+statement_timeout: '1m'
+sslmode: 'require'
+environments:
+  dev: {}
+  prod:
+    connection:
+      host: 'db.example.com'
+      password_file: 'secret'
+    lock_timeout: '1s'
+    lock_retries: 3
+    sslmode: 'verify-full'
+    require_confirmation: true
+";
+
+    #[test]
+    fn test_env_override() {
+        let file = write_config("env-override", &format!("version: 0.2\n...\n{}{}", SETTINGS, ENVIRONMENTS));
+
+        let dev = Config::parse_settings(file.clone(), Some("dev".to_string())).unwrap();
+
+        assert_eq!(dev.database_url.as_deref(), Some("postgres://localhost/app"));
+        assert!(dev.connection_params.is_empty());
+        assert_eq!(dev.timeouts.lock_timeout.as_deref(), Some("5s"));
+        assert_eq!(dev.timeouts.lock_retries, 0);
+        assert_eq!(dev.tls.mode.as_deref(), Some("require"));
+        assert!(!dev.require_confirmation);
+
+        let prod = Config::parse_settings(file.clone(), Some("prod".to_string())).unwrap();
+
+        // the connection is replaced as a whole, the rest entry by entry
+        assert_eq!(prod.database_url, None);
+        assert_eq!(prod.connection_params.host.as_deref(), Some("db.example.com"));
+        assert_eq!(
+            prod.connection_params.password_file.as_ref().map(|path| path.as_path()),
+            Some(file.parent_dir().unwrap().join("secret").as_path())
+        );
+        assert_eq!(prod.timeouts.lock_timeout.as_deref(), Some("1s"));
+        assert_eq!(prod.timeouts.statement_timeout.as_deref(), Some("1m"));
+        assert_eq!(prod.timeouts.lock_retries, 3);
+        assert_eq!(prod.tls.mode.as_deref(), Some("verify-full"));
+        assert!(prod.require_confirmation);
+        assert_eq!(prod.env.as_deref(), Some("prod"));
+    }
+}
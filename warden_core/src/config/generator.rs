@@ -23,7 +23,7 @@ pub fn generate_initial(driver: &str) -> Result<String, Error> {
 
     let driver = String::from(driver);
 
-    let version = Fraction::new(1u8, 10u8);
+    let version = Fraction::new(2u8, 10u8);
     let connection = IncognitumValue::new(Cow::from("DATABASE_URL")).set_tag(Cow::from(env::TAG));
     let schema = Yamlette::new();
 
@@ -45,14 +45,5 @@ pub fn generate_initial(driver: &str) -> Result<String, Error> {
 }
 
 fn validate_driver(name: &str) -> bool {
-    driver::drivers(|factory| {
-        if factory.name() == name {
-            Some(Ok(true))
-        } else {
-            None
-        }
-    })
-    .ok()
-    .unwrap()
-    .unwrap_or(false)
+    driver::lookup(name).is_some()
 }
@@ -1,5 +1,20 @@
+pub mod action_log;
+pub mod capabilities;
 pub mod driver;
 pub mod connection;
+pub mod external;
+pub mod history;
+pub mod params;
+pub mod registration;
+pub mod timeouts;
+pub mod tls;
 
+pub use action_log::{ActionLogEntry, ActionLogFilter};
+pub use capabilities::Capabilities;
 pub use driver::Driver;
 pub use connection::Connection;
+pub use history::{HistoryEntry, HistoryFilter};
+pub use params::ConnectionParams;
+pub use registration::Registration;
+pub use timeouts::Timeouts;
+pub use tls::Tls;
@@ -0,0 +1,28 @@
+use chrono::prelude::{DateTime, FixedOffset};
+
+/// A record of the database action log
+#[derive(Debug, Clone)]
+pub struct ActionLogEntry {
+    pub id: i64,
+    pub ts: DateTime<FixedOffset>,
+    pub user: String,
+
+    /// None for the local (unix socket) connections
+    pub client_address: Option<String>,
+
+    pub session_pid: i32,
+    pub action: String,
+}
+
+/// Which action log records to fetch
+#[derive(Debug, Clone, Default)]
+pub struct ActionLogFilter {
+    pub since: Option<DateTime<FixedOffset>>,
+    pub user: Option<String>,
+
+    /// Regular expression the action must match
+    pub grep: Option<String>,
+
+    /// Only the records following this one (used to poll for the new records)
+    pub after_id: Option<i64>,
+}
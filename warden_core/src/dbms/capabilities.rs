@@ -0,0 +1,65 @@
+use failure::Error;
+
+/// What the driver supports, so that the unsupported modes are refused upfront
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    /// DDL statements are rolled back along with the transaction (required to deploy atomically)
+    pub transactional_ddl: bool,
+
+    /// Concurrent deployments are serialised with a lock
+    pub advisory_locking: bool,
+
+    /// Migrations can be deployed outside of a transaction (`transaction: none`)
+    pub non_transactional: bool,
+
+    /// Snapshots of the migrations are kept along with their registrations
+    pub snapshot_storage: bool,
+
+    /// Deployed migrations can be reverted
+    pub revert: bool,
+}
+
+impl Default for Capabilities {
+    /// Everything is supported (as it is with PostgreSQL)
+    fn default() -> Self {
+        Capabilities {
+            transactional_ddl: true,
+            advisory_locking: true,
+            non_transactional: true,
+            snapshot_storage: true,
+            revert: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Nothing is supported
+    pub fn none() -> Self {
+        Capabilities {
+            transactional_ddl: false,
+            advisory_locking: false,
+            non_transactional: false,
+            snapshot_storage: false,
+            revert: false,
+        }
+    }
+
+    /// Human readable names of the capabilities along with whether they are supported
+    pub fn list(&self) -> [(&'static str, bool); 5] {
+        [
+            ("transactional DDL", self.transactional_ddl),
+            ("advisory locking", self.advisory_locking),
+            ("non-transactional execution", self.non_transactional),
+            ("snapshot storage", self.snapshot_storage),
+            ("revert", self.revert),
+        ]
+    }
+}
+
+/// The error of the capability the driver lacks, followed by the consequence
+pub fn unsupported(driver: &str, capability: &str, consequence: &str) -> Error {
+    failure::err_msg(format!(
+        "The \"{}\" driver does not support {}, {}",
+        driver, capability, consequence
+    ))
+}
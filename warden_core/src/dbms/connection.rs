@@ -1,10 +1,57 @@
 use failure::Error;
+use crate::dbms::{ActionLogEntry, ActionLogFilter, HistoryEntry, HistoryFilter, Registration, Timeouts};
 use crate::migration::meta::Meta;
+use crate::migration::snapshot::Snapshot;
+use std::collections::BTreeSet;
+use std::time::Duration;
 
 pub trait Connection {
     fn get_catalog(&self) -> &str;
 
     fn get_last_deployed_migration(&self) -> Result<Option<u128>, Error>;
 
-    fn deploy(&self, meta: Meta) -> Result<(), Error>;
+    /// IDs of all the migrations registered in the database (deployed or not)
+    fn get_registered_migrations(&self) -> Result<BTreeSet<u128>, Error>;
+
+    /// IDs of all the deployed migrations
+    fn get_deployed_migrations(&self) -> Result<BTreeSet<u128>, Error>;
+
+    /// The source and the seal of a migration as they were registered
+    fn get_registered_migration(&self, id: u128) -> Result<Option<Registration>, Error>;
+
+    /// Registered migrations ordered by their IDs
+    fn get_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, Error>;
+
+    /// The snapshot of the migration folder taken when it was registered
+    fn get_migration_snapshot(&self, id: u128) -> Result<Option<Snapshot>, Error>;
+
+    /// Take the lock preventing concurrent deployments.
+    /// Waits indefinitely when no timeout given (zero timeout means no waiting)
+    fn lock(&self, timeout: Option<Duration>) -> Result<(), Error>;
+
+    fn unlock(&self) -> Result<(), Error>;
+
+    /// Deploy the migration within the given timeouts (ignored for the initial migration)
+    fn deploy(&self, meta: Meta, timeouts: &Timeouts) -> Result<(), Error>;
+
+    /// Deploy all the migrations within a single transaction, the timeouts
+    /// are the defaults overridden by the migrations themselves.
+    /// On failure nothing is deployed and the position of the failed migration
+    /// is returned along with the error (equals to the number of migrations
+    /// when the commit itself has failed)
+    fn deploy_atomic(&self, migrations: Vec<Meta>, timeouts: &Timeouts) -> Result<(), (usize, Error)>;
+
+    /// Apply the pending upgrades of the internal warden schema.
+    /// Returns the names of the applied upgrades
+    fn upgrade(&self) -> Result<Vec<String>, Error>;
+
+    /// Record an action in the database log
+    fn log_action(&self, action: &str) -> Result<(), Error>;
+
+    /// Records of the action log ordered by their IDs
+    fn get_action_log(&self, filter: &ActionLogFilter) -> Result<Vec<ActionLogEntry>, Error>;
+
+    /// Execute the revert source registered along with the migration
+    /// and mark the migration as not deployed
+    fn revert(&self, meta: Meta) -> Result<(), Error>;
 }
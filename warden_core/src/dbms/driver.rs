@@ -1,17 +1,102 @@
-use path_abs::PathDir;
+use path_abs::{PathDir, PathFile};
 use failure::Error;
 use lazy_static::lazy_static;
+use std::io;
 use std::sync::Mutex;
-use crate::dbms::Connection;
+use crate::dbms::{external, Capabilities, Connection, ConnectionParams, Tls};
+use crate::migration::execution::ExecutionMode;
+use crate::migration::transaction::TransactionMode;
+use crate::migration::{algo::Algo, identity::Identity, meta::Meta, path::FileOrDir, seal::SealMeta};
+use crate::sewer::Sewer;
 
 pub trait Driver {
     fn name(&self) -> &'static str;
 
+    /// What the driver supports, everything by default
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
     /// Create initial migration in the folder
     fn create_initial_migration(&self, folder: &PathDir) -> Result<(), Error>;
 
-    /// Opens a new connection to RDBMS and returns it
-    fn open_connection(&self, url: &str) -> Result<Box<Connection>, Error>;
+    /// Opens a new connection to RDBMS and returns it.
+    /// The warden bookkeeping data is kept in the registry schema
+    fn open_connection(&self, url: &str, registry_schema: &str) -> Result<Box<Connection>, Error>;
+
+    /// Whether the error of opening the connection may go away by itself
+    /// (e.g. the server is not up yet), so that connecting is worth retrying.
+    /// By default only the refused and broken connections are
+    fn is_transient_error(&self, error: &Error) -> bool {
+        error.iter_chain().any(|cause| match cause.downcast_ref::<io::Error>() {
+            Some(error) => matches!(
+                error.kind(),
+                io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+                    | io::ErrorKind::TimedOut
+            ),
+            None => false,
+        })
+    }
+
+    /// Opens a new connection with the settings of the config file:
+    /// the URL or the connection params, along with the TLS settings.
+    /// Only the URL (with TLS disabled, if anything) is supported by default
+    fn open_configured_connection(
+        &self,
+        url: Option<&str>,
+        params: &ConnectionParams,
+        tls: &Tls,
+        registry_schema: &str,
+    ) -> Result<Box<Connection>, Error> {
+        if !tls.is_empty() && !tls.is_disabled() {
+            Err(failure::err_msg(format!(
+                "The \"{}\" driver does not support the TLS settings of the config file",
+                self.name()
+            )))?
+        }
+
+        if !params.is_empty() {
+            Err(failure::err_msg(format!(
+                "The \"{}\" driver does not support the connection fields, use the connection URL instead",
+                self.name()
+            )))?
+        }
+
+        let url = url.ok_or_else(|| failure::err_msg("Database connection URL is undefined"))?;
+
+        self.open_connection(url, registry_schema)
+    }
+}
+
+/// Create the initial migration of the source in the folder, built and sealed
+/// (for the drivers not shipping their own)
+pub fn create_initial_migration(folder: &PathDir, source: &str) -> Result<(), Error> {
+    let root = PathDir::create(folder.join("000000--warden-init"))?;
+    let sql = PathDir::create(root.join("sql"))?;
+    PathFile::create(sql.join("warden.sql"))?.write_str(source)?;
+
+    let meta = Meta::create(
+        Meta::default_yaml_format_version(),
+        PathFile::create(root.join("meta.yml"))?,
+        Identity::build("000000".to_string(), "warden-init".to_string()),
+        SealMeta::build(root.join("seal.yml"), Algo::default()),
+        FileOrDir::from(sql),
+        root.join("migration.sql"),
+        None,
+        TransactionMode::default(),
+        ExecutionMode::default(),
+        None,
+        None,
+    )?;
+
+    let sewer = Sewer::new(meta.clone())?;
+    let migration = sewer.sewage(&sewer.sew_up()?)?;
+    PathFile::create(meta.get_target())?.write_str(&migration)?;
+
+    meta.get_seal_meta().make(migration.as_bytes(), None)
 }
 
 pub trait DriverFactory: Sync + Send {
@@ -45,7 +130,12 @@ where
     Ok(None)
 }
 
+/// The driver of the name, or the external one (`external:<command>`)
 pub fn lookup(name: &str) -> Option<Box<Driver>> {
+    if let Some(command) = name.strip_prefix(external::PREFIX) {
+        return Some(Box::new(external::External::new(command)));
+    }
+
     drivers(|factory| {
         if factory.name() == name {
             Some(Ok(factory.new()))
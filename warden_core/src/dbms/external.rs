@@ -0,0 +1,696 @@
+//! The driver delegating to a helper process, configured as `external:<command>`
+//! (the command is split by whitespaces, no quoting supported).
+//!
+//! Warden spawns the command once per connection and talks to it over
+//! its stdin and stdout, one JSON object per line. The stderr of the helper
+//! is left to the terminal, so it can log there.
+//!
+//! Every request gets exactly one response, in order:
+//!
+//! ```text
+//! -> {"method": "<method>", "params": {...}}
+//! <- {"result": <value>}
+//! <- {"error": "<message>"}
+//! ```
+//!
+//! The migrations are identified by their uids (e.g. `"000000"`), timestamps
+//! are RFC 3339 strings and binary data (seals and snapshots) is hex encoded.
+//! A migration is passed as an object of `uid`, `name`, `source`, `revert_source`
+//! (or null), `seal` (`timestamp`, `algo`, `sign`, `revert_sign`), `snapshot`
+//! (`format`, `data`), `transaction` (`single` or `none`), `execution`
+//! (`server` or `client`). The `timeouts` are passed as `lock_timeout`,
+//! `statement_timeout` (both as configured, e.g. `"5s"`, or null) and `lock_retries`.
+//!
+//! | method              | params                                  | result                                    |
+//! |---------------------|-----------------------------------------|-------------------------------------------|
+//! | `capabilities`      |                                         | see below                                 |
+//! | `open`              | `url`, `registry_schema`                | null                                      |
+//! | `catalog`           |                                         | the database name                         |
+//! | `initial_migration` |                                         | `source` of the initial migration         |
+//! | `last_deployed`     |                                         | uid or null                               |
+//! | `registered`        |                                         | list of uids                              |
+//! | `deployed`          |                                         | list of uids                              |
+//! | `registration`      | `uid`                                   | `name`, `source` and `seal`, or null      |
+//! | `history`           | `since`, `pending`, `limit`             | list of `uid`, `name`, `registered_at`, `deployed_at`, `partially_applied_at`, `seal_algo`, `seal_sign` |
+//! | `snapshot`          | `uid`                                   | `format` and `data`, or null              |
+//! | `lock`              | `timeout_ms` (null to wait indefinitely) | null                                     |
+//! | `unlock`            |                                         | null                                      |
+//! | `register`          | migration                               | null                                      |
+//! | `deploy`            | `uid`, `timeouts`                       | null                                      |
+//! | `deploy_atomic`     | `migrations` (along with `timeouts`)    | null                                      |
+//! | `upgrade`           |                                         | list of applied upgrades                  |
+//! | `log`               | `action`                                | null                                      |
+//! | `action_log`        | `since`, `user`, `grep`, `after_id`     | list of `id`, `ts`, `user`, `client_address`, `session_pid`, `action` |
+//! | `revert`            | `uid`                                   | null                                      |
+//!
+//! The `capabilities` are asked for once, with no connection opened. The result
+//! turns on any of `transactional_ddl`, `advisory_locking`, `non_transactional`,
+//! `snapshot_storage` and `revert` (see `dbms::Capabilities`) set to true,
+//! so that the CLI refuses the modes depending on the rest; the omitted ones are
+//! considered unsupported, as is everything if the helper fails to respond.
+//!
+//! A migration is always registered right before it is deployed. Should the
+//! deployment of a transactional migration fail, the helper is expected to
+//! drop its registration. The error of `deploy_atomic` may carry the `position`
+//! of the failed migration.
+
+use crate::dbms::{self, driver, ActionLogEntry, ActionLogFilter, Capabilities, Driver, HistoryEntry, HistoryFilter, Registration, Timeouts};
+use crate::migration::algo::Algo;
+use crate::migration::base36;
+use crate::migration::identity::Identity;
+use crate::migration::meta::Meta;
+use crate::migration::seal::Seal;
+use crate::migration::snapshot::{self, Snapshot};
+use chrono::prelude::{DateTime, FixedOffset};
+use failure::{Error, ResultExt};
+use path_abs::{FileRead, PathDir};
+use serde_json::{json, Value};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+/// The prefix of the driver names
+pub const PREFIX: &str = "external:";
+
+static NAME: &'static str = "external";
+
+pub struct External {
+    command: String,
+
+    /// Fetched on the first demand
+    capabilities: Cell<Option<Capabilities>>,
+}
+
+impl External {
+    pub fn new(command: &str) -> External {
+        External {
+            command: command.to_string(),
+            capabilities: Cell::new(None),
+        }
+    }
+
+    fn fetch_capabilities(&self) -> Result<Capabilities, Error> {
+        let helper = Helper::spawn(&self.command)?;
+        let result = helper.call("capabilities", json!({}))?;
+
+        if !result.is_object() {
+            Err(helper.invalid("capabilities", "expected an object"))?
+        }
+
+        let supported = |name| result.get(name).and_then(Value::as_bool).unwrap_or(false);
+
+        Ok(Capabilities {
+            transactional_ddl: supported("transactional_ddl"),
+            advisory_locking: supported("advisory_locking"),
+            non_transactional: supported("non_transactional"),
+            snapshot_storage: supported("snapshot_storage"),
+            revert: supported("revert"),
+        })
+    }
+}
+
+impl Driver for External {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        if let Some(capabilities) = self.capabilities.get() {
+            return capabilities;
+        }
+
+        let capabilities = self.fetch_capabilities().unwrap_or_else(|error| {
+            log::warn!(
+                "Could not fetch the capabilities of the external driver, considering none supported: {}",
+                error
+            );
+
+            Capabilities::none()
+        });
+
+        self.capabilities.set(Some(capabilities));
+
+        capabilities
+    }
+
+    fn create_initial_migration(&self, folder: &PathDir) -> Result<(), Error> {
+        let helper = Helper::spawn(&self.command)?;
+        let result = helper.call("initial_migration", json!({}))?;
+        let source = get_str(&result, "source")?;
+
+        driver::create_initial_migration(folder, source)
+    }
+
+    fn open_connection(&self, url: &str, registry_schema: &str) -> Result<Box<dbms::Connection>, Error> {
+        let helper = Helper::spawn(&self.command)?;
+
+        helper.call("open", json!({ "url": url, "registry_schema": registry_schema }))?;
+
+        let catalog = helper.call("catalog", json!({}))?;
+
+        let catalog = catalog
+            .as_str()
+            .ok_or_else(|| helper.invalid("catalog", "expected a string"))?
+            .to_string();
+
+        Ok(Box::new(Connection {
+            helper: helper,
+            catalog: catalog,
+        }))
+    }
+}
+
+/// The helper process
+struct Helper {
+    command: String,
+    process: RefCell<Process>,
+}
+
+struct Process {
+    child: Child,
+
+    /// None once closed
+    stdin: Option<ChildStdin>,
+
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Helper {
+    fn spawn(command: &str) -> Result<Helper, Error> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| failure::err_msg("The command of the external driver is undefined"))?;
+
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|_| format!("Could not run the external driver \"{}\"", command))?;
+
+        let stdin = child.stdin.take();
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| failure::err_msg("Could not read the output of the external driver"))?;
+
+        Ok(Helper {
+            command: command.to_string(),
+            process: RefCell::new(Process {
+                child: child,
+                stdin: stdin,
+                stdout: BufReader::new(stdout),
+            }),
+        })
+    }
+
+    /// Send the request and return the result, or fail with the error responded
+    fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let mut response = self.request(method, params)?;
+
+        if let Some(error) = get_error(&response) {
+            Err(error)?
+        }
+
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(self.invalid(method, "neither result nor error given")),
+        }
+    }
+
+    /// Send the request and wait for the response
+    fn request(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let mut process = self.process.borrow_mut();
+        let process = &mut *process;
+
+        let request = json!({ "method": method, "params": params });
+        log::trace!("External driver request: {}", request);
+
+        let stdin = process
+            .stdin
+            .as_mut()
+            .ok_or_else(|| failure::err_msg("The input of the external driver is closed"))?;
+
+        writeln!(stdin, "{}", request)
+            .and_then(|()| stdin.flush())
+            .with_context(|_| format!("The external driver \"{}\" has exited", self.command))?;
+
+        let mut line = String::new();
+
+        if process.stdout.read_line(&mut line)? == 0 {
+            Err(failure::err_msg(format!(
+                "The external driver \"{}\" has exited without responding to \"{}\"",
+                self.command, method
+            )))?
+        }
+
+        log::trace!("External driver response: {}", line.trim_end());
+
+        let response: Value = serde_json::from_str(&line).map_err(|error| self.invalid(method, &error.to_string()))?;
+
+        if !response.is_object() {
+            Err(self.invalid(method, "expected an object"))?
+        }
+
+        Ok(response)
+    }
+
+    fn invalid(&self, method: &str, reason: &str) -> Error {
+        failure::err_msg(format!(
+            "Invalid response of the external driver \"{}\" to \"{}\": {}",
+            self.command, method, reason
+        ))
+    }
+}
+
+impl Drop for Helper {
+    /// Closing the input tells the helper to exit
+    fn drop(&mut self) {
+        let process = self.process.get_mut();
+        process.stdin.take();
+
+        if let Err(error) = process.child.wait() {
+            log::warn!("Could not wait for the external driver \"{}\" to exit: {}", self.command, error);
+        }
+    }
+}
+
+pub struct Connection {
+    helper: Helper,
+    catalog: String,
+}
+
+impl dbms::Connection for Connection {
+    fn get_catalog(&self) -> &str {
+        &self.catalog
+    }
+
+    fn get_last_deployed_migration(&self) -> Result<Option<u128>, Error> {
+        let result = self.helper.call("last_deployed", json!({}))?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+
+        Ok(Some(parse_uid(result.as_str().ok_or_else(|| self.helper.invalid("last_deployed", "expected a uid"))?)?))
+    }
+
+    fn get_registered_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        self.call_uids("registered")
+    }
+
+    fn get_deployed_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        self.call_uids("deployed")
+    }
+
+    fn get_registered_migration(&self, id: u128) -> Result<Option<Registration>, Error> {
+        let result = self.helper.call("registration", json!({ "uid": uid(id) }))?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+
+        let seal = result.get("seal").ok_or_else(|| missing("seal"))?;
+
+        Ok(Some(Registration {
+            identity: Identity::build(uid(id), get_str(&result, "name")?.to_string()),
+            source: get_str(&result, "source")?.to_string(),
+            seal: Seal {
+                timestamp: parse_ts(get_str(seal, "timestamp")?)?,
+                algo: parse_algo(get_str(seal, "algo")?)?,
+                sign: decode_hex(get_str(seal, "sign")?)?,
+                revert_sign: get_opt_str(seal, "revert_sign")?.map(decode_hex).transpose()?,
+            },
+        }))
+    }
+
+    fn get_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, Error> {
+        let result = self.helper.call(
+            "history",
+            json!({
+                "since": filter.since.as_ref().map(DateTime::to_rfc3339),
+                "pending": filter.pending,
+                "limit": filter.limit,
+            }),
+        )?;
+
+        let mut entries = Vec::new();
+
+        for entry in get_array(&result)? {
+            entries.push(HistoryEntry {
+                identity: Identity::build(get_str(entry, "uid")?.to_string(), get_str(entry, "name")?.to_string()),
+                registered_at: parse_ts(get_str(entry, "registered_at")?)?,
+                deployed_at: get_opt_str(entry, "deployed_at")?.map(parse_ts).transpose()?,
+                partially_applied_at: get_opt_str(entry, "partially_applied_at")?.map(parse_ts).transpose()?,
+                seal_algo: get_opt_str(entry, "seal_algo")?.map(str::to_string),
+                seal_sign: get_opt_str(entry, "seal_sign")?.map(decode_hex).transpose()?,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn get_migration_snapshot(&self, id: u128) -> Result<Option<Snapshot>, Error> {
+        let result = self.helper.call("snapshot", json!({ "uid": uid(id) }))?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+
+        Ok(Some(Snapshot {
+            format: snapshot::Format::from(get_str(&result, "format")?)?,
+            data: decode_hex(get_str(&result, "data")?)?,
+        }))
+    }
+
+    fn lock(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        self.helper.call(
+            "lock",
+            json!({ "timeout_ms": timeout.map(|timeout| timeout.as_millis() as u64) }),
+        )?;
+
+        Ok(())
+    }
+
+    fn unlock(&self) -> Result<(), Error> {
+        self.helper.call("unlock", json!({}))?;
+
+        Ok(())
+    }
+
+    fn deploy(&self, meta: Meta, timeouts: &Timeouts) -> Result<(), Error> {
+        self.helper.call("register", migration(&meta)?)?;
+        self.helper.call(
+            "deploy",
+            json!({ "uid": meta.get_identity().get_uid(), "timeouts": timeouts_json(timeouts) }),
+        )?;
+
+        Ok(())
+    }
+
+    fn deploy_atomic(&self, migrations: Vec<Meta>, timeouts: &Timeouts) -> Result<(), (usize, Error)> {
+        let mut params = Vec::with_capacity(migrations.len());
+
+        for (position, meta) in migrations.iter().enumerate() {
+            let mut migration = migration(meta).map_err(|error| (position, error))?;
+            migration["timeouts"] = timeouts_json(&timeouts.for_migration(meta));
+            params.push(migration);
+        }
+
+        let response = self
+            .helper
+            .request("deploy_atomic", json!({ "migrations": params }))
+            .map_err(|error| (0, error))?;
+
+        if let Some(error) = get_error(&response) {
+            let position = response
+                .get("position")
+                .and_then(Value::as_u64)
+                .unwrap_or(0);
+
+            return Err((position as usize, error));
+        }
+
+        Ok(())
+    }
+
+    fn upgrade(&self) -> Result<Vec<String>, Error> {
+        let result = self.helper.call("upgrade", json!({}))?;
+
+        get_array(&result)?
+            .iter()
+            .map(|upgrade| {
+                upgrade
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| self.helper.invalid("upgrade", "expected a list of strings"))
+            })
+            .collect()
+    }
+
+    fn log_action(&self, action: &str) -> Result<(), Error> {
+        self.helper.call("log", json!({ "action": action }))?;
+
+        Ok(())
+    }
+
+    fn get_action_log(&self, filter: &ActionLogFilter) -> Result<Vec<ActionLogEntry>, Error> {
+        let result = self.helper.call(
+            "action_log",
+            json!({
+                "since": filter.since.as_ref().map(DateTime::to_rfc3339),
+                "user": filter.user,
+                "grep": filter.grep,
+                "after_id": filter.after_id,
+            }),
+        )?;
+
+        let mut entries = Vec::new();
+
+        for entry in get_array(&result)? {
+            entries.push(ActionLogEntry {
+                id: get_i64(entry, "id")?,
+                ts: parse_ts(get_str(entry, "ts")?)?,
+                user: get_str(entry, "user")?.to_string(),
+                client_address: get_opt_str(entry, "client_address")?.map(str::to_string),
+                session_pid: get_i64(entry, "session_pid")? as i32,
+                action: get_str(entry, "action")?.to_string(),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    fn revert(&self, meta: Meta) -> Result<(), Error> {
+        self.helper.call("revert", json!({ "uid": meta.get_identity().get_uid() }))?;
+
+        Ok(())
+    }
+}
+
+impl Connection {
+    fn call_uids(&self, method: &str) -> Result<BTreeSet<u128>, Error> {
+        let result = self.helper.call(method, json!({}))?;
+
+        get_array(&result)?
+            .iter()
+            .map(|uid| {
+                uid.as_str()
+                    .ok_or_else(|| self.helper.invalid(method, "expected a list of uids"))
+                    .and_then(parse_uid)
+            })
+            .collect()
+    }
+}
+
+/// The migration as it is passed to the helper
+fn migration(meta: &Meta) -> Result<Value, Error> {
+    let snapshot = Snapshot::take(snapshot::Format::TarGz, meta)?;
+    let seal = meta.get_seal_meta().read_the_seal()?;
+
+    let revert_source = if let Some(revert) = meta.get_revert() {
+        Some(FileRead::read(revert.get_target())?.read_string()?)
+    } else {
+        None
+    };
+
+    Ok(json!({
+        "uid": meta.get_identity().get_uid(),
+        "name": meta.get_identity().get_name(),
+        "source": FileRead::read(meta.get_target())?.read_string()?,
+        "revert_source": revert_source,
+        "seal": {
+            "timestamp": seal.timestamp.to_rfc3339(),
+            "algo": seal.algo.stringify(),
+            "sign": encode_hex(&seal.sign),
+            "revert_sign": seal.revert_sign.as_ref().map(|sign| encode_hex(sign)),
+        },
+        "snapshot": {
+            "format": snapshot.format.as_str(),
+            "data": encode_hex(&snapshot.data),
+        },
+        "transaction": meta.get_transaction().stringify(),
+        "execution": meta.get_execution().stringify(),
+    }))
+}
+
+fn timeouts_json(timeouts: &Timeouts) -> Value {
+    json!({
+        "lock_timeout": timeouts.lock_timeout,
+        "statement_timeout": timeouts.statement_timeout,
+        "lock_retries": timeouts.lock_retries,
+    })
+}
+
+fn uid(id: u128) -> String {
+    format!("{:0>6}", base36::encode(id))
+}
+
+fn parse_uid(uid: &str) -> Result<u128, Error> {
+    base36::decode(uid).ok_or_else(|| failure::err_msg(format!("Invalid migration uid \"{}\"", uid)))
+}
+
+fn parse_ts(ts: &str) -> Result<DateTime<FixedOffset>, Error> {
+    Ok(DateTime::parse_from_rfc3339(ts).with_context(|_| format!("Invalid timestamp \"{}\"", ts))?)
+}
+
+fn parse_algo(algo: &str) -> Result<Algo, Error> {
+    Algo::from_str(algo).ok_or_else(|| failure::err_msg(format!("unknown algo {}", algo)))
+}
+
+/// The error responded, if any
+fn get_error(response: &Value) -> Option<Error> {
+    match response.get("error") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(error)) => Some(failure::err_msg(error.clone())),
+        Some(error) => Some(failure::err_msg(error.to_string())),
+    }
+}
+
+fn missing(field: &str) -> Error {
+    failure::err_msg(format!("The external driver has not provided \"{}\"", field))
+}
+
+fn get_array(value: &Value) -> Result<&Vec<Value>, Error> {
+    value
+        .as_array()
+        .ok_or_else(|| failure::err_msg("The external driver has provided no list"))
+}
+
+fn get_str<'a>(value: &'a Value, field: &str) -> Result<&'a str, Error> {
+    get_opt_str(value, field)?.ok_or_else(|| missing(field))
+}
+
+fn get_opt_str<'a>(value: &'a Value, field: &str) -> Result<Option<&'a str>, Error> {
+    match value.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(string)) => Ok(Some(string)),
+        Some(_) => Err(failure::err_msg(format!(
+            "The external driver has provided \"{}\" which is not a string",
+            field
+        ))),
+    }
+}
+
+fn get_i64(value: &Value, field: &str) -> Result<i64, Error> {
+    value
+        .get(field)
+        .and_then(Value::as_i64)
+        .ok_or_else(|| missing(field))
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(data: &str) -> Result<Vec<u8>, Error> {
+    data.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| failure::err_msg(format!("Invalid hex \"{}\"", data)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use path_abs::PathFile;
+
+    /// The driver of the helper answering with the responses given by method, each spawn and request logged into the root
+    fn fake_helper(name: &str, responses: &[(&str, &str)]) -> (External, PathDir) {
+        let (command, root) = testing::external_helper(name, responses);
+
+        (External::new(&command), root)
+    }
+
+    fn read_log(root: &PathDir, log: &str) -> Vec<String> {
+        PathFile::new(root.join(log)).unwrap().read_string().unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let (driver, root) = fake_helper(
+            "capabilities",
+            &[("capabilities", r#"{"result": {"transactional_ddl": true, "revert": true, "advisory_locking": false}}"#)],
+        );
+
+        let expected = Capabilities {
+            transactional_ddl: true,
+            advisory_locking: false,
+            non_transactional: false,
+            snapshot_storage: false,
+            revert: true,
+        };
+
+        assert_eq!(driver.capabilities(), expected);
+        assert_eq!(driver.capabilities(), expected);
+
+        assert_eq!(read_log(&root, "spawns").len(), 1);
+        assert_eq!(read_log(&root, "requests"), vec![r#"{"method":"capabilities","params":{}}"#]);
+
+        let (driver, root) = fake_helper("capabilities-error", &[]);
+
+        assert_eq!(driver.capabilities(), Capabilities::none());
+        assert_eq!(driver.capabilities(), Capabilities::none());
+        assert_eq!(read_log(&root, "spawns").len(), 1);
+
+        assert_eq!(External::new("warden-no-such-helper").capabilities(), Capabilities::none());
+    }
+
+    #[test]
+    fn test_connection() {
+        let (driver, root) = fake_helper(
+            "connection",
+            &[
+                ("open", r#"{"result": null}"#),
+                ("catalog", r#"{"result": "app"}"#),
+                ("last_deployed", r#"{"result": "00000a"}"#),
+                ("registered", r#"{"result": ["000000", "00000a"]}"#),
+                ("deployed", r#"{"result": "000000"}"#),
+            ],
+        );
+
+        let connection = driver.open_connection("fake://app", "warden").unwrap();
+
+        assert_eq!(connection.get_catalog(), "app");
+        assert_eq!(connection.get_last_deployed_migration().unwrap(), Some(10));
+        assert_eq!(connection.get_registered_migrations().unwrap(), vec![0, 10].into_iter().collect());
+        assert!(connection.get_deployed_migrations().is_err());
+
+        let error = connection.unlock().unwrap_err();
+        assert_eq!(error.to_string(), "unknown method");
+
+        drop(connection);
+
+        assert_eq!(read_log(&root, "spawns").len(), 1);
+        assert_eq!(
+            read_log(&root, "requests"),
+            vec![
+                r#"{"method":"open","params":{"registry_schema":"warden","url":"fake://app"}}"#,
+                r#"{"method":"catalog","params":{}}"#,
+                r#"{"method":"last_deployed","params":{}}"#,
+                r#"{"method":"registered","params":{}}"#,
+                r#"{"method":"deployed","params":{}}"#,
+                r#"{"method":"unlock","params":{}}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(encode_hex(&[0, 15, 16, 255]), "000f10ff");
+        assert_eq!(decode_hex("000f10FF").unwrap(), vec![0, 15, 16, 255]);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+        assert!(decode_hex("\u{e9}0").is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::migration::identity::Identity;
+use chrono::prelude::{DateTime, FixedOffset};
+
+/// A registered migration along with its lifecycle timestamps
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub identity: Identity,
+
+    pub registered_at: DateTime<FixedOffset>,
+
+    /// None if the migration has not been deployed
+    pub deployed_at: Option<DateTime<FixedOffset>>,
+
+    /// The time of the failed deployment outside of a transaction (if any)
+    pub partially_applied_at: Option<DateTime<FixedOffset>>,
+
+    pub seal_algo: Option<String>,
+    pub seal_sign: Option<Vec<u8>>,
+}
+
+/// Which migrations to fetch from the history
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Only the migrations registered or deployed since the moment
+    pub since: Option<DateTime<FixedOffset>>,
+
+    /// Only the migrations that have not been deployed yet
+    pub pending: bool,
+
+    /// Only the latest migrations
+    pub limit: Option<u32>,
+}
@@ -0,0 +1,90 @@
+use path_abs::PathArc;
+use std::fmt;
+
+/// Connection settings given field by field instead of the URL,
+/// for the driver to connect with
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionParams {
+    /// The host name, or the folder of the unix socket
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub dbname: Option<String>,
+
+    /// The file keeping the password (on its first line)
+    pub password_file: Option<PathArc>,
+
+    /// The file of the passwords per host and database (e.g. ~/.pgpass)
+    pub passfile: Option<PathArc>,
+}
+
+impl ConnectionParams {
+    pub fn is_empty(&self) -> bool {
+        *self == ConnectionParams::default()
+    }
+}
+
+impl fmt::Display for ConnectionParams {
+    /// `user@host:port/dbname`, omitting the undefined parts
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref user) = self.user {
+            write!(f, "{}@", user)?;
+        }
+
+        write!(f, "{}", self.host.as_deref().unwrap_or("localhost"))?;
+
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+
+        if let Some(ref dbname) = self.dbname {
+            write!(f, "/{}", dbname)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(ConnectionParams::default().to_string(), "localhost");
+
+        let params = ConnectionParams {
+            host: Some("db".to_string()),
+            port: Some(5433),
+            user: Some("deploy".to_string()),
+            dbname: Some("app".to_string()),
+            password_file: Some(PathArc::new("/run/secrets/db")),
+            passfile: None,
+        };
+        assert_eq!(params.to_string(), "deploy@db:5433/app");
+
+        let params = ConnectionParams {
+            host: Some("/var/run/postgresql".to_string()),
+            dbname: Some("app".to_string()),
+            ..ConnectionParams::default()
+        };
+        assert_eq!(params.to_string(), "/var/run/postgresql/app");
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(ConnectionParams::default().is_empty());
+
+        let params = ConnectionParams {
+            passfile: Some(PathArc::new(".pgpass")),
+            ..ConnectionParams::default()
+        };
+        assert!(!params.is_empty());
+
+        let params = ConnectionParams {
+            port: Some(5432),
+            ..ConnectionParams::default()
+        };
+        assert!(!params.is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+use crate::migration::identity::Identity;
+use crate::migration::seal::Seal;
+
+/// A migration as it has been registered in the database
+#[derive(Debug, Clone)]
+pub struct Registration {
+    pub identity: Identity,
+
+    /// The built migration SQL
+    pub source: String,
+
+    pub seal: Seal,
+}
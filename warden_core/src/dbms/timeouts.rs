@@ -0,0 +1,29 @@
+use crate::migration::meta::Meta;
+
+/// Timeouts applied to the migrations while they are being deployed.
+/// The values are passed to the DBMS as they are (e.g. "5s" for PostgreSQL)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timeouts {
+    pub lock_timeout: Option<String>,
+    pub statement_timeout: Option<String>,
+
+    /// How many times to retry a migration failed because of the lock timeout
+    pub lock_retries: u32,
+}
+
+impl Timeouts {
+    /// The defaults overridden with the values defined in the migration meta
+    pub fn for_migration(&self, meta: &Meta) -> Timeouts {
+        Timeouts {
+            lock_timeout: meta
+                .get_lock_timeout()
+                .map(str::to_string)
+                .or_else(|| self.lock_timeout.clone()),
+            statement_timeout: meta
+                .get_statement_timeout()
+                .map(str::to_string)
+                .or_else(|| self.statement_timeout.clone()),
+            lock_retries: self.lock_retries,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use path_abs::PathArc;
+
+/// TLS settings of the config file.
+/// The drivers take them as defaults, letting the connection URL override them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tls {
+    /// disable, prefer, require, verify-ca or verify-full
+    pub mode: Option<String>,
+
+    /// The certificate authorities to verify the server certificate with
+    pub root_cert: Option<PathArc>,
+
+    /// The client certificate
+    pub cert: Option<PathArc>,
+
+    /// The private key of the client certificate
+    pub key: Option<PathArc>,
+}
+
+impl Tls {
+    pub fn is_empty(&self) -> bool {
+        *self == Tls::default()
+    }
+
+    /// TLS is turned off with no certificates given, which the drivers without TLS support comply with
+    pub fn is_disabled(&self) -> bool {
+        self.mode.as_deref() == Some("disable") && self.root_cert.is_none() && self.cert.is_none() && self.key.is_none()
+    }
+}
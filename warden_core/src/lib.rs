@@ -1,6 +1,10 @@
+pub mod build;
 pub mod config;
 pub mod dbms;
 pub mod migration;
 pub mod path;
 pub mod time;
 pub mod sewer;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
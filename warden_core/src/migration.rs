@@ -1,10 +1,13 @@
 pub mod algo;
 pub mod base36;
 pub mod builder;
+pub mod execution;
 pub mod fs;
 pub mod identity;
 pub mod meta;
 pub mod path;
+pub mod revert;
 pub mod seal;
 pub mod snapshot;
+pub mod transaction;
 pub mod weight;
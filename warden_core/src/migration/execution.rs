@@ -0,0 +1,33 @@
+/// Where the statements of a migration are executed from
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExecutionMode {
+    /// The registered source is executed by the database itself
+    Server,
+
+    /// The migration is split into statements that are sent one by one
+    /// by the client (within the deploy transaction)
+    Client,
+}
+
+impl ExecutionMode {
+    pub fn stringify(self) -> &'static str {
+        match self {
+            ExecutionMode::Server => "server",
+            ExecutionMode::Client => "client",
+        }
+    }
+
+    pub fn from_str(string: &str) -> Option<ExecutionMode> {
+        match string {
+            "server" => Some(ExecutionMode::Server),
+            "client" => Some(ExecutionMode::Client),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ExecutionMode {
+    fn default() -> ExecutionMode {
+        ExecutionMode::Server
+    }
+}
@@ -1,16 +1,86 @@
 use super::algo::Algo;
+use super::execution::ExecutionMode;
 use super::identity::Identity;
 use super::path::FileOrDir;
+use super::revert::RevertMeta;
 use super::seal::SealMeta;
+use super::transaction::TransactionMode;
 use crate::path;
 use failure::{self, Error};
-use path_abs::{PathArc, PathDir, PathFile};
+use path_abs::{PathAbs, PathArc, PathDir, PathFile};
 
+use std::borrow::Cow;
 use yamlette::model::schema::yamlette::Yamlette;
+use yamlette::model::style::{CommonStyles, Style};
 use yamlette::model::yaml::str::FORCE_QUOTES;
 use yamlette::model::Fraction;
+use yamlette::orchestra::chord::Chord;
+use yamlette::orchestra::{OrchError, Orchestra};
 use yamlette::yamlette;
 
+/// Writes the meta document, the blocks are the optional entries
+/// of "structure" and of the top level map
+macro_rules! meta_yamlette {
+    (
+        $schema:ident ; $version:ident ;
+        $uid:ident, $name:ident, $source:ident, $target:ident, $seal_file:ident, $seal_algo:ident ;
+        { $( $skey:tt => $sval:ident ),* } ;
+        { $( $key:tt => $val:ident ),* }
+    ) => {
+        yamlette!(
+            write ;
+            [
+            [ { "version": $version } ],
+            [ {
+                "identity": {
+                    "uid": (# FORCE_QUOTES => $uid),
+                    "name": (# FORCE_QUOTES => $name)
+                },
+
+                "structure": {
+                    "source": (# FORCE_QUOTES => $source),
+                    "target": (# FORCE_QUOTES => $target)
+                    $( , (Optional($sval.as_ref().map(|_| $skey))) : (# FORCE_QUOTES => Optional($sval.clone())) )*
+                },
+
+                "seal": {
+                    "file": (# FORCE_QUOTES => $seal_file),
+                    "algo": (# FORCE_QUOTES => $seal_algo)
+                }
+
+                $( , (Optional($val.as_ref().map(|_| $key))) : (# FORCE_QUOTES => Optional($val.clone())) )*
+            } ]
+            ]
+            ; { schema: $schema }
+        )
+    };
+}
+
+/// A node written only when defined, since yamlette cannot write
+/// optional values (both the key and the value of an entry are optional,
+/// so that the entry is skipped altogether)
+struct Optional<T>(Option<T>);
+
+impl<T: Chord> Chord for Optional<T> {
+    fn chord_size(&self) -> usize {
+        self.0.as_ref().map(Chord::chord_size).unwrap_or(0)
+    }
+
+    fn play(
+        self,
+        orchestra: &Orchestra,
+        level: usize,
+        alias: Option<Cow<'static, str>>,
+        styles: CommonStyles,
+        value_styles: &mut [&mut Style],
+    ) -> Result<(), OrchError> {
+        match self.0 {
+            Some(value) => value.play(orchestra, level, alias, styles, value_styles),
+            None => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Meta {
     yaml_format_version: Fraction,
@@ -19,6 +89,11 @@ pub struct Meta {
     seal_meta: SealMeta,
     source: FileOrDir,
     target: PathArc,
+    revert: Option<RevertMeta>,
+    transaction: TransactionMode,
+    execution: ExecutionMode,
+    lock_timeout: Option<String>,
+    statement_timeout: Option<String>,
 }
 
 impl Meta {
@@ -42,18 +117,34 @@ impl Meta {
         &self.target
     }
 
+    /// The revert of the migration, None unless declared and given some patches
+    /// (so that an empty revert tree is never built, sealed and registered as the revert)
+    pub fn get_revert(&self) -> Option<&RevertMeta> {
+        self.revert.as_ref().filter(|revert| revert.has_patches())
+    }
+
+    pub fn get_transaction(&self) -> TransactionMode {
+        self.transaction
+    }
+
+    pub fn get_execution(&self) -> ExecutionMode {
+        self.execution
+    }
+
+    pub fn get_lock_timeout(&self) -> Option<&str> {
+        self.lock_timeout.as_ref().map(String::as_str)
+    }
+
+    pub fn get_statement_timeout(&self) -> Option<&str> {
+        self.statement_timeout.as_ref().map(String::as_str)
+    }
+
     pub fn get_path(&self) -> &PathFile {
         &self.path
     }
 
     pub fn get_source_base(&self) -> PathDir {
-        self.source.map(
-            |file| {
-                file.parent_dir()
-                    .expect("every file must have a parent folder")
-            },
-            |dir| dir.clone(),
-        )
+        self.source.get_base()
     }
 
     pub fn get_base(&self) -> PathDir {
@@ -62,6 +153,35 @@ impl Meta {
             .expect("every file must have a parent folder")
     }
 
+    /// Make sure the built migration (and its revert) still match the seal
+    pub fn verify_seal(&self) -> Result<(), Error> {
+        let seal = self.seal_meta.read_the_seal()?;
+
+        let target = PathFile::new(&self.target)?;
+        if !seal.verify(target.read_string()?.as_bytes()) {
+            Err(failure::err_msg(format!(
+                "Migration \"{}\": {} does not match the seal {}",
+                self.identity,
+                path::printable(&target),
+                path::printable(self.seal_meta.get_file())
+            )))?
+        }
+
+        if let Some(revert) = self.get_revert() {
+            let target = PathFile::new(revert.get_target())?;
+            if !seal.verify_revert(target.read_string()?.as_bytes()) {
+                Err(failure::err_msg(format!(
+                    "Migration \"{}\": {} does not match the seal {}",
+                    self.identity,
+                    path::printable(&target),
+                    path::printable(self.seal_meta.get_file())
+                )))?
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn get_yaml_format_version(&self) -> &Fraction {
         &self.yaml_format_version
     }
@@ -73,6 +193,11 @@ impl Meta {
         seal_meta: SealMeta,
         source: FileOrDir,
         target: PathArc,
+        revert: Option<RevertMeta>,
+        transaction: TransactionMode,
+        execution: ExecutionMode,
+        lock_timeout: Option<String>,
+        statement_timeout: Option<String>,
     ) -> Result<Meta, Error> {
         let meta = Self {
             yaml_format_version: yaml_format_version,
@@ -81,6 +206,11 @@ impl Meta {
             seal_meta: seal_meta,
             source: source,
             target: target,
+            revert: revert,
+            transaction: transaction,
+            execution: execution,
+            lock_timeout: lock_timeout,
+            statement_timeout: statement_timeout,
         };
         meta.save()
     }
@@ -109,28 +239,42 @@ impl Meta {
         let seal_file = path::relpath_to_base(&root, self.seal_meta.get_file()).replace("./", "");
         let seal_algo = self.seal_meta.get_algo().stringify();
 
-        Ok(yamlette!(
-            write ;
-            [
-            [ { "version": syntax_version } ],
-            [ {
-                "identity": {
-                    "uid": (# FORCE_QUOTES => uid),
-                    "name": (# FORCE_QUOTES => name)
-                },
+        // optional entries are only written when they are defined,
+        // so that the existing meta files do not change when re-saved
+        let revert_source = self
+            .revert
+            .as_ref()
+            .map(|revert| path::relpath_to_base(&root, &revert.get_source().get_path()).replace("./", ""));
+        let revert_target = self
+            .revert
+            .as_ref()
+            .map(|revert| path::relpath_to_base(&root, revert.get_target()).replace("./", ""));
+
+        let transaction = match self.transaction {
+            TransactionMode::Single => None,
+            mode => Some(mode.stringify()),
+        };
 
-                "structure": {
-                    "source": (# FORCE_QUOTES => source),
-                    "target": (# FORCE_QUOTES => target)
-                },
+        let execution = match self.execution {
+            ExecutionMode::Server => None,
+            mode => Some(mode.stringify()),
+        };
 
-                "seal": {
-                    "file": (# FORCE_QUOTES => seal_file),
-                    "algo": (# FORCE_QUOTES => seal_algo)
-                }
-            } ]
-            ]
-            ; { schema: schema }
+        let lock_timeout = self.lock_timeout.clone();
+        let statement_timeout = self.statement_timeout.clone();
+
+        Ok(meta_yamlette!(
+            schema ; syntax_version ; uid, name, source, target, seal_file, seal_algo ;
+            {
+                "revert_source" => revert_source,
+                "revert_target" => revert_target
+            } ;
+            {
+                "transaction" => transaction,
+                "execution" => execution,
+                "lock_timeout" => lock_timeout,
+                "statement_timeout" => statement_timeout
+            }
         )?)
     }
 
@@ -178,13 +322,20 @@ impl Meta {
 
                 "structure" => {
                     "source" => (source:String),
-                    "target" => (target:String)
+                    "target" => (target:String),
+                    "revert_source" => (revert_source:String),
+                    "revert_target" => (revert_target:String)
                 },
 
                 "seal" => {
                     "file" => (seal_file:String),
                     "algo" => (seal_algo:String)
-                }
+                },
+
+                "transaction" => (transaction:String),
+                "execution" => (execution:String),
+                "lock_timeout" => (lock_timeout:String),
+                "statement_timeout" => (statement_timeout:String)
             } ]] ;
             { schema: schema }
         );
@@ -203,24 +354,42 @@ impl Meta {
             )))?,
         );
 
-        let source_path = root
-            .join(source.ok_or(failure::err_msg("structure.source is undefined"))?)
-            .absolute()?;
-
-        let source_path = if let Ok(source_path) = PathFile::new(&source_path) {
-            Ok(FileOrDir::from(source_path))
-        } else if let Ok(source_path) = PathDir::new(&source_path) {
-            Ok(FileOrDir::from(source_path))
-        } else {
-            Err(failure::err_msg(format!(
-                "source path does not exist: \"{:?}\"",
-                source_path
-            )))
-        }?;
+        let source_path = file_or_dir(
+            root.join(source.ok_or(failure::err_msg("structure.source is undefined"))?)
+                .absolute()?,
+        )?;
 
         let target_path =
             root.join(target.ok_or(failure::err_msg("structure.target is undefined"))?);
 
+        let revert = match (revert_source, revert_target) {
+            (Some(revert_source), Some(revert_target)) => Some(RevertMeta::build(
+                file_or_dir(root.join(revert_source).absolute()?)?,
+                root.join(revert_target),
+            )),
+            (None, None) => None,
+            (Some(_), None) => Err(failure::err_msg("structure.revert_target is undefined"))?,
+            (None, Some(_)) => Err(failure::err_msg("structure.revert_source is undefined"))?,
+        };
+
+        let transaction = if let Some(transaction) = transaction {
+            TransactionMode::from_str(&transaction).ok_or(failure::err_msg(format!(
+                "unsupported transaction: \"{}\"",
+                transaction
+            )))?
+        } else {
+            TransactionMode::default()
+        };
+
+        let execution = if let Some(execution) = execution {
+            ExecutionMode::from_str(&execution).ok_or(failure::err_msg(format!(
+                "unsupported execution: \"{}\"",
+                execution
+            )))?
+        } else {
+            ExecutionMode::default()
+        };
+
         Self::create(
             Fraction::new(1u8, 10u8),
             file,
@@ -228,6 +397,85 @@ impl Meta {
             seal_meta,
             source_path,
             target_path,
+            revert,
+            transaction,
+            execution,
+            lock_timeout,
+            statement_timeout,
         )
     }
 }
+
+fn file_or_dir(path: PathAbs) -> Result<FileOrDir, Error> {
+    if let Ok(file) = PathFile::new(&path) {
+        Ok(FileOrDir::from(file))
+    } else if let Ok(dir) = PathDir::new(&path) {
+        Ok(FileOrDir::from(dir))
+    } else {
+        Err(failure::err_msg(format!(
+            "source path does not exist: \"{:?}\"",
+            path
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn create(root: &PathDir, revert: bool, transaction: TransactionMode, lock_timeout: Option<&str>) -> Meta {
+        Meta::create(
+            Meta::default_yaml_format_version(),
+            PathFile::create(root.join("meta.yml")).unwrap(),
+            Identity::build("3tgu50".to_string(), "test".to_string()),
+            SealMeta::build(root.join("seal.yml"), Algo::default()),
+            FileOrDir::from(PathDir::create_all(root.join("sql")).unwrap()),
+            root.join("migration.sql"),
+            if revert {
+                Some(RevertMeta::build(
+                    FileOrDir::from(PathDir::create_all(root.join("revert")).unwrap()),
+                    root.join("revert-migration.sql"),
+                ))
+            } else {
+                None
+            },
+            transaction,
+            ExecutionMode::default(),
+            lock_timeout.map(str::to_string),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_save_optional_entries() {
+        let root = testing::temp_dir("meta-optional-entries");
+
+        create(&root, false, TransactionMode::Single, None);
+        let plain = PathFile::new(root.join("meta.yml")).unwrap().read_string().unwrap();
+
+        assert!(!plain.contains("revert_source"));
+        assert!(!plain.contains("transaction"));
+        assert!(!plain.contains("lock_timeout"));
+
+        create(&root, true, TransactionMode::None, Some("1s'2"));
+        let full = PathFile::new(root.join("meta.yml")).unwrap().read_string().unwrap();
+
+        assert!(full.contains("  revert_source: 'revert'\n  revert_target: 'revert-migration.sql'\n"));
+        assert!(full.contains("\ntransaction: 'none'\n"));
+        assert!(full.contains("\nlock_timeout: '1s''2'\n"));
+        assert!(!full.contains("execution"));
+        assert!(!full.contains("statement_timeout"));
+
+        let meta = Meta::open(PathFile::new(root.join("meta.yml")).unwrap()).unwrap();
+
+        assert!(meta.revert.is_some());
+        assert_eq!(meta.get_transaction(), TransactionMode::None);
+        assert_eq!(meta.get_execution(), ExecutionMode::Server);
+        assert_eq!(meta.get_lock_timeout(), Some("1s'2"));
+        assert_eq!(meta.get_statement_timeout(), None);
+
+        root.remove_all().unwrap();
+    }
+}
@@ -18,6 +18,17 @@ impl FileOrDir {
         }
     }
 
+    /// The folder the source is based at (the parent dir for a single file)
+    pub fn get_base(&self) -> PathDir {
+        self.map(
+            |file| {
+                file.parent_dir()
+                    .expect("every file must have a parent folder")
+            },
+            |dir| dir.clone(),
+        )
+    }
+
     pub fn get_path(&self) -> PathAbs {
         match self.path {
             Left(ref file) => {
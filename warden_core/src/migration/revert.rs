@@ -0,0 +1,41 @@
+use super::path::FileOrDir;
+use path_abs::PathArc;
+use walkdir::WalkDir;
+
+/// Location of the revert (down) source tree of a migration and its build target
+#[derive(Debug, Clone)]
+pub struct RevertMeta {
+    source: FileOrDir,
+    target: PathArc,
+}
+
+impl RevertMeta {
+    pub fn build(source: FileOrDir, target: PathArc) -> Self {
+        Self {
+            source: source,
+            target: target,
+        }
+    }
+
+    pub fn get_source(&self) -> &FileOrDir {
+        &self.source
+    }
+
+    pub fn get_target(&self) -> &PathArc {
+        &self.target
+    }
+
+    /// Whether the source tree has any patches to build the revert of
+    pub fn has_patches(&self) -> bool {
+        self.source.map(
+            |_| true,
+            |dir| {
+                WalkDir::new(dir.as_path())
+                    .min_depth(1)
+                    .into_iter()
+                    .filter_map(|entry| entry.ok())
+                    .any(|entry| entry.file_type().is_file() && entry.path().extension().map_or(false, |ext| ext == "sql"))
+            },
+        )
+    }
+}
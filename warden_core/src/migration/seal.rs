@@ -14,6 +14,23 @@ pub struct Seal {
     pub timestamp: DateTime,
     pub algo: Algo,
     pub sign: Vec<u8>,
+    pub revert_sign: Option<Vec<u8>>,
+}
+
+impl Seal {
+    /// Check the content is the one that has been sealed
+    pub fn verify(&self, content: &[u8]) -> bool {
+        self.algo.hash(content) == self.sign
+    }
+
+    /// Check the revert content is the one that has been sealed
+    pub fn verify_revert(&self, content: &[u8]) -> bool {
+        if let Some(ref revert_sign) = self.revert_sign {
+            &self.algo.hash(content) == revert_sign
+        } else {
+            false
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -38,12 +55,14 @@ impl SealMeta {
         &self.file
     }
 
-    pub fn make(&self, content: &[u8]) -> Result<(), Error> {
+    /// Seal up the migration content, along with its revert content if there is one
+    pub fn make(&self, content: &[u8], revert: Option<&[u8]>) -> Result<(), Error> {
         let hash = self.get_algo().hash(content);
-        Ok(PathFile::create(self.get_file())?.write_str(&self.yamlette(hash)?)?)
+        let revert_hash = revert.map(|revert| self.get_algo().hash(revert));
+        Ok(PathFile::create(self.get_file())?.write_str(&self.yamlette(hash, revert_hash)?)?)
     }
 
-    fn yamlette(&self, hash: Vec<u8>) -> Result<String, Error> {
+    fn yamlette(&self, hash: Vec<u8>, revert_hash: Option<Vec<u8>>) -> Result<String, Error> {
         let version = Fraction::new(1u8, 10u8);
         let schema = Yamlette::new();
 
@@ -51,6 +70,26 @@ impl SealMeta {
         let hash = BinaryValue::from(hash);
         let time = time::yamlette_timestamp_value();
 
+        if let Some(revert_hash) = revert_hash {
+            let revert_hash = BinaryValue::from(revert_hash);
+
+            return Ok(yamlette!(
+                write ;
+                [
+                    [ { "version": version } ],
+                    [ {
+                        "timestamp": (# ISSUE_TAG => time),
+                        "seal": {
+                            "algo": (# FORCE_QUOTES => algo),
+                            "sign": (# ISSUE_TAG => hash),
+                            "revert": (# ISSUE_TAG => revert_hash)
+                        }
+                    } ]
+                ]
+                ; { schema: schema }
+            )?);
+        }
+
         Ok(yamlette!(
             write ;
             [
@@ -77,7 +116,8 @@ impl SealMeta {
                 "timestamp" => (timestamp:DateTime),
                 "seal" => {
                     "algo" => (algo:String),
-                    "sign" => (sign:Vec<u8>)
+                    "sign" => (sign:Vec<u8>),
+                    "revert" => (revert_sign:Vec<u8>)
                 }
             }]] ;
             { schema: schema }
@@ -105,6 +145,7 @@ impl SealMeta {
             timestamp,
             algo,
             sign,
+            revert_sign,
         })
     }
 
@@ -2,7 +2,8 @@
 
 use super::meta::Meta;
 use failure::Error;
-use libflate::gzip::Encoder;
+use libflate::gzip::{Decoder, Encoder};
+use path_abs::PathDir;
 use std::fmt::{self, Display, Formatter};
 use tar;
 
@@ -56,6 +57,18 @@ impl Snapshot {
         Ok(Self { format, data })
     }
 
+    /// Unpack the archive into the folder (the migration folder is created within it)
+    pub fn unpack(&self, into: &PathDir) -> Result<(), Error> {
+        match self.format {
+            Format::TarGz => {
+                let decoder = Decoder::new(&self.data[..])?;
+                tar::Archive::new(decoder).unpack(into)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn tar_gz(meta: &Meta) -> Result<Vec<u8>, Error> {
         let mut enc = Encoder::new(Vec::new())?;
 
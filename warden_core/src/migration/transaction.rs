@@ -0,0 +1,33 @@
+/// How a migration is wrapped into a transaction when deployed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TransactionMode {
+    /// Registration and deployment happen in a single transaction
+    Single,
+
+    /// The migration is executed outside of any transaction,
+    /// so it may be left partially applied if it fails
+    None,
+}
+
+impl TransactionMode {
+    pub fn stringify(self) -> &'static str {
+        match self {
+            TransactionMode::Single => "single",
+            TransactionMode::None => "none",
+        }
+    }
+
+    pub fn from_str(string: &str) -> Option<TransactionMode> {
+        match string {
+            "single" => Some(TransactionMode::Single),
+            "none" => Some(TransactionMode::None),
+            _ => None,
+        }
+    }
+}
+
+impl Default for TransactionMode {
+    fn default() -> TransactionMode {
+        TransactionMode::Single
+    }
+}
@@ -5,6 +5,7 @@ pub mod patch;
 pub mod raw_map;
 
 use crate::migration::meta::Meta;
+use crate::migration::path::FileOrDir;
 use crate::path;
 use crate::time;
 use failure::Error;
@@ -30,13 +31,30 @@ pub struct Sewer {
 
 impl Sewer {
     pub fn new(meta: Meta) -> Result<Sewer, Error> {
-        let raw_map = RawMap::new(&meta)?;
-        let source_base = meta.get_source_base();
+        let source = meta.get_source().clone();
+        Self::from_source(meta, &source)
+    }
+
+    /// Sewer for the revert source tree of the migration
+    /// Returns None if the migration does not declare one
+    pub fn revert(meta: Meta) -> Result<Option<Sewer>, Error> {
+        let source = if let Some(revert) = meta.get_revert() {
+            revert.get_source().clone()
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self::from_source(meta, &source)?))
+    }
+
+    fn from_source(meta: Meta, source: &FileOrDir) -> Result<Sewer, Error> {
+        let raw_map = RawMap::new(&meta, source)?;
+        let source_base = source.get_base();
 
         // add extra dependencies (e.g. parent nodes)
         let raw_map = Self::raw_map_update(&source_base, raw_map)?;
 
-        let map = Map::from_raw(&meta, &raw_map)?;
+        let map = Map::from_raw(source, &raw_map)?;
 
         Ok(Sewer { map: map, meta })
     }
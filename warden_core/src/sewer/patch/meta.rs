@@ -1,7 +1,7 @@
 use super::super::raw_map::RawMap;
 use super::raw_meta::RawMeta;
 use super::Patch;
-use crate::migration::meta::Meta as MigrationMeta;
+use crate::migration::path::FileOrDir;
 use crate::path;
 use failure::Error;
 use fraction::BigFraction;
@@ -30,12 +30,12 @@ impl Meta {
     }
 
     pub fn from_raw(
-        meta: &MigrationMeta,
+        source: &FileOrDir,
         raw_map: &RawMap,
         patch: &Patch,
         raw: &RawMeta,
     ) -> Result<Self, Error> {
-        let source_base = meta.get_source_base();
+        let source_base = source.get_base();
         let requirements = Self::build_requirements(&source_base, patch, raw_map, &raw)?;
 
         Ok(Meta {
@@ -1,5 +1,6 @@
 use super::patch::{raw_meta::RawMeta, Patch};
 use crate::migration::meta::Meta;
+use crate::migration::path::FileOrDir;
 use crate::path;
 use failure::Error;
 use path_abs::{PathDir, PathFile};
@@ -27,8 +28,8 @@ impl RawMap {
         }
     }
 
-    pub fn new(meta: &Meta) -> Result<RawMap, Error> {
-        meta.get_source().map(
+    pub fn new(meta: &Meta, source: &FileOrDir) -> Result<RawMap, Error> {
+        source.map(
             |file| Self::from_file(meta, file),
             |dir| Self::from_dir(meta, dir),
         )
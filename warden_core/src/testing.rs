@@ -0,0 +1,131 @@
+//! Helpers of the tests, shared with the crates depending on warden_core
+//! (enabled by the `testing` feature of their dev-dependencies)
+
+use crate::migration::execution::ExecutionMode;
+use crate::migration::revert::RevertMeta;
+use crate::migration::transaction::TransactionMode;
+use crate::migration::{algo::Algo, identity::Identity, meta::Meta, path::FileOrDir, seal::SealMeta};
+use crate::sewer::Sewer;
+use path_abs::{PathDir, PathFile};
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An empty temporary folder, unique to the call (the tests run in parallel)
+pub fn temp_dir(name: &str) -> PathDir {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let path = env::temp_dir().join(format!(
+        "warden-{}-{}-{}",
+        name,
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let _ = fs::remove_dir_all(&path);
+
+    PathDir::create_all(path).unwrap()
+}
+
+/// The file of the content, accessible by its owner only (as the password files must be)
+pub fn private_file(dir: &PathDir, name: &str, content: &str) -> PathFile {
+    let file = PathFile::create(dir.join(name)).unwrap();
+    file.write_str(content).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+    }
+
+    file
+}
+
+/// The migration of the patch, built and sealed (an unsealed draft if there is no patch)
+pub fn create_migration(migrations: &PathDir, token: &str, patch: Option<&str>) -> Meta {
+    create(migrations, token, patch, None)
+}
+
+/// The same as `create_migration`, declaring the revert tree along with the revert patch
+/// (built and sealed with the migration), or with no patches in it
+pub fn create_migration_with_revert(migrations: &PathDir, token: &str, patch: Option<&str>, revert: Option<&str>) -> Meta {
+    create(migrations, token, patch, Some(revert))
+}
+
+fn create(migrations: &PathDir, token: &str, patch: Option<&str>, revert: Option<Option<&str>>) -> Meta {
+    let root = PathDir::create(migrations.join(token)).unwrap();
+    let source = PathDir::create(root.join("sql")).unwrap();
+
+    let revert = revert.map(|patch| {
+        let source = PathDir::create(root.join("revert")).unwrap();
+
+        if let Some(patch) = patch {
+            PathFile::create(source.join("patch.sql")).unwrap().write_str(patch).unwrap();
+        }
+
+        RevertMeta::build(FileOrDir::from(source), root.join("revert-migration.sql"))
+    });
+
+    let meta = Meta::create(
+        Meta::default_yaml_format_version(),
+        PathFile::create(root.join("meta.yml")).unwrap(),
+        Identity::from_str(token).unwrap(),
+        SealMeta::build(root.join("seal.yml"), Algo::default()),
+        FileOrDir::from(source.clone()),
+        root.join("migration.sql"),
+        revert,
+        TransactionMode::default(),
+        ExecutionMode::default(),
+        None,
+        None,
+    )
+    .unwrap();
+
+    if let Some(patch) = patch {
+        PathFile::create(source.join("patch.sql")).unwrap().write_str(patch).unwrap();
+
+        let sewer = Sewer::new(meta.clone()).unwrap();
+        let migration = sewer.sewage(&sewer.sew_up().unwrap()).unwrap();
+
+        PathFile::create(meta.get_target()).unwrap().write_str(&migration).unwrap();
+
+        let revert = Sewer::revert(meta.clone()).unwrap().map(|sewer| {
+            let revert = sewer.sewage(&sewer.sew_up().unwrap()).unwrap();
+            PathFile::create(meta.get_revert().unwrap().get_target()).unwrap().write_str(&revert).unwrap();
+            revert
+        });
+
+        meta.get_seal_meta()
+            .make(migration.as_bytes(), revert.as_ref().map(|revert| revert.as_bytes()))
+            .unwrap();
+    }
+
+    meta
+}
+
+/// The command of the external driver answering with the responses given by method
+/// (and with an error to the rest), along with the folder it logs each spawn and request into
+pub fn external_helper(name: &str, responses: &[(&str, &str)]) -> (String, PathDir) {
+    let root = temp_dir(name);
+
+    let mut script = format!("echo spawn >> {}/spawns\n", root.display());
+    script.push_str(&format!(
+        "while read -r line; do\n  echo \"$line\" >> {}/requests\n  case \"$line\" in\n",
+        root.display()
+    ));
+
+    for (method, response) in responses {
+        script.push_str(&format!("    *'\"method\":\"{}\"'*) echo '{}' ;;\n", method, response));
+    }
+
+    script.push_str("    *) echo '{\"error\": \"unknown method\"}' ;;\n  esac\ndone\n");
+
+    let helper = PathFile::create(root.join("helper.sh")).unwrap();
+    helper.write_str(&script).unwrap();
+
+    for log in &["spawns", "requests"] {
+        PathFile::create(root.join(log)).unwrap().write_str("").unwrap();
+    }
+
+    (format!("sh {}", helper.display()), root)
+}
@@ -0,0 +1,619 @@
+use failure::Error;
+
+use crate::database::{self, Database, Deployment, Migration};
+use chrono::prelude::{DateTime, FixedOffset, Utc};
+use path_abs::FileRead;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::env;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use warden_core::dbms::{ActionLogEntry, ActionLogFilter, HistoryEntry, HistoryFilter, Registration, Timeouts};
+use warden_core::migration::base36;
+use warden_core::migration::snapshot::{self, Snapshot};
+use warden_core::migration::transaction::TransactionMode;
+use warden_core::{dbms, migration::meta::Meta};
+
+/// How often to retry while waiting for the lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The error of the failures scripted in the URL
+const SCRIPTED_FAILURE: &str = "Scripted failure";
+
+/// The source of the connection ids, telling the lock holders apart
+static CONNECTIONS: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug)]
+pub struct Connection {
+    id: u64,
+
+    /// The database name
+    catalog: String,
+
+    database: Arc<Mutex<Database>>,
+}
+
+impl dbms::Connection for Connection {
+    fn get_catalog(&self) -> &str {
+        &self.catalog
+    }
+
+    fn get_last_deployed_migration(&self) -> Result<Option<u128>, Error> {
+        Ok(self.get_deployed_migrations()?.iter().next_back().cloned())
+    }
+
+    fn get_registered_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        Ok(self.database().get_migrations().keys().cloned().collect())
+    }
+
+    fn get_deployed_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        Ok(self
+            .database()
+            .get_migrations()
+            .iter()
+            .filter(|(_, migration)| migration.deployed_at.is_some())
+            .map(|(id, _)| *id)
+            .collect())
+    }
+
+    fn get_registered_migration(&self, id: u128) -> Result<Option<Registration>, Error> {
+        Ok(self
+            .database()
+            .get_migration(id)
+            .map(|migration| migration.registration.clone()))
+    }
+
+    fn get_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, Error> {
+        let database = self.database();
+
+        let mut result: Vec<HistoryEntry> = database
+            .get_migrations()
+            .values()
+            .filter(|migration| {
+                filter
+                    .since
+                    .map(|since| {
+                        migration.registered_at >= since
+                            || migration.deployed_at.map(|ts| ts >= since).unwrap_or(false)
+                    })
+                    .unwrap_or(true)
+            })
+            .filter(|migration| !filter.pending || migration.deployed_at.is_none())
+            .map(|migration| HistoryEntry {
+                identity: migration.registration.identity.clone(),
+                registered_at: migration.registered_at,
+                deployed_at: migration.deployed_at,
+                partially_applied_at: migration.partially_applied_at,
+                seal_algo: Some(migration.registration.seal.algo.stringify().to_string()),
+                seal_sign: Some(migration.registration.seal.sign.clone()),
+            })
+            .collect();
+
+        if let Some(limit) = filter.limit {
+            let skip = result.len().saturating_sub(limit as usize);
+            result.drain(..skip);
+        }
+
+        Ok(result)
+    }
+
+    fn get_migration_snapshot(&self, id: u128) -> Result<Option<Snapshot>, Error> {
+        let database = self.database();
+
+        let migration = if let Some(migration) = database.get_migration(id) {
+            migration
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(Snapshot {
+            format: snapshot::Format::from(&migration.snapshot_format)?,
+            data: migration.snapshot_data.clone(),
+        }))
+    }
+
+    fn lock(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        let started = Instant::now();
+
+        loop {
+            {
+                let mut database = self.database();
+
+                match database.lock_holder {
+                    None => {
+                        database.lock_holder = Some(self.id);
+                        return Ok(());
+                    }
+                    Some(holder) if holder == self.id => return Ok(()),
+                    Some(holder) => {
+                        if timeout.map(|timeout| started.elapsed() >= timeout).unwrap_or(false) {
+                            return Err(failure::err_msg(format!(
+                                "Could not acquire the lock on the \"{}\" database, it is held by connection {}",
+                                self.catalog, holder
+                            )));
+                        }
+                    }
+                }
+            }
+
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+
+    fn unlock(&self) -> Result<(), Error> {
+        let mut database = self.database();
+
+        if database.lock_holder == Some(self.id) {
+            database.lock_holder = None;
+        }
+
+        Ok(())
+    }
+
+    fn deploy(&self, meta: Meta, timeouts: &Timeouts) -> Result<(), Error> {
+        let source = read_source(&meta)?;
+
+        let mut database = self.database();
+        let result = self.apply(&mut database, &meta, &source);
+
+        database.record(Deployment {
+            meta: meta,
+            source: source,
+            timeouts: timeouts.clone(),
+            atomic: false,
+            rolled_back: false,
+            error: result.as_ref().err().map(|error| error.to_string()),
+        });
+
+        result
+    }
+
+    fn deploy_atomic(&self, migrations: Vec<Meta>, timeouts: &Timeouts) -> Result<(), (usize, Error)> {
+        if let Some(position) = migrations
+            .iter()
+            .position(|meta| meta.get_transaction() == TransactionMode::None)
+        {
+            return Err((
+                position,
+                failure::err_msg(format!(
+                    r#"Migration "{}" is not transactional and cannot be deployed atomically"#,
+                    migrations[position].get_identity()
+                )),
+            ));
+        }
+
+        let mut database = self.database();
+        let state = database.clone();
+
+        let mut deployments = Vec::with_capacity(migrations.len());
+        let mut failure = None;
+
+        for (position, meta) in migrations.into_iter().enumerate() {
+            let result = read_source(&meta).and_then(|source| {
+                let result = self.apply(&mut database, &meta, &source);
+
+                deployments.push(Deployment {
+                    timeouts: timeouts.for_migration(&meta),
+                    meta: meta,
+                    source: source,
+                    atomic: true,
+                    rolled_back: false,
+                    error: result.as_ref().err().map(|error| error.to_string()),
+                });
+
+                result
+            });
+
+            if let Err(error) = result {
+                failure = Some((position, error));
+                break;
+            }
+        }
+
+        if failure.is_some() {
+            database.rollback(state);
+
+            for deployment in &mut deployments {
+                deployment.rolled_back = deployment.error.is_none();
+            }
+        }
+
+        for deployment in deployments {
+            database.record(deployment);
+        }
+
+        failure.map(Err).unwrap_or(Ok(()))
+    }
+
+    fn upgrade(&self) -> Result<Vec<String>, Error> {
+        if !self.database().is_initialised() {
+            return Err(failure::err_msg(
+                "The database is not initialised with Warden, deploy the initial migration instead",
+            ));
+        }
+
+        // the registry is kept in memory, there is nothing to upgrade
+        Ok(Vec::new())
+    }
+
+    fn log_action(&self, action: &str) -> Result<(), Error> {
+        let mut database = self.database();
+
+        if !database.is_initialised() {
+            log::warn!("The database is not initialised with Warden, could not log: {}", action);
+            return Ok(());
+        }
+
+        log(&mut database, action);
+
+        Ok(())
+    }
+
+    fn get_action_log(&self, filter: &ActionLogFilter) -> Result<Vec<ActionLogEntry>, Error> {
+        let grep = filter.grep.as_ref().map(|grep| Regex::new(grep)).transpose()?;
+
+        Ok(self
+            .database()
+            .get_action_log()
+            .iter()
+            .filter(|entry| filter.since.map(|since| entry.ts >= since).unwrap_or(true))
+            .filter(|entry| filter.user.as_ref().map(|user| &entry.user == user).unwrap_or(true))
+            .filter(|entry| grep.as_ref().map(|grep| grep.is_match(&entry.action)).unwrap_or(true))
+            .filter(|entry| filter.after_id.map(|id| entry.id > id).unwrap_or(true))
+            .cloned()
+            .collect())
+    }
+
+    fn revert(&self, meta: Meta) -> Result<(), Error> {
+        let id = get_id(&meta)?;
+
+        if id == 0 {
+            return Err(failure::err_msg("The initial migration cannot be reverted"));
+        }
+
+        let mut database = self.database();
+
+        if !database.is_initialised() {
+            return Err(failure::err_msg(format!(
+                r#"The database is not initialised with Warden. Error trying to revert migration "{}""#,
+                meta.get_identity()
+            )));
+        }
+
+        let migration = database
+            .get_migration_mut(id)
+            .ok_or_else(|| failure::err_msg(format!("Unknown migration with \"{}\"", base36::encode(id))))?;
+
+        if migration.deployed_at.is_none() {
+            Err(failure::err_msg(format!(
+                "Migration {} has not been deployed",
+                meta.get_identity()
+            )))?
+        }
+
+        if migration.revert_source.is_none() {
+            Err(failure::err_msg(format!(
+                "Migration {} has no revert source",
+                meta.get_identity()
+            )))?
+        }
+
+        migration.deployed_at = None;
+        log(&mut database, &format!("Reverted | {}", meta.get_identity()));
+
+        Ok(())
+    }
+}
+
+impl Drop for Connection {
+    /// The lock belongs to the session, as it does with the real databases
+    fn drop(&mut self) {
+        if let Ok(mut database) = self.database.lock() {
+            if database.lock_holder == Some(self.id) {
+                database.lock_holder = None;
+            }
+        }
+    }
+}
+
+impl Connection {
+    fn database(&self) -> MutexGuard<'_, Database> {
+        self.database.lock().unwrap()
+    }
+
+    /// Register and deploy the migration, or fail it the way it has been scripted.
+    /// A failed migration is left registered only when it is not transactional
+    fn apply(&self, database: &mut Database, meta: &Meta, source: &str) -> Result<(), Error> {
+        let id = get_id(meta)?;
+
+        if id != 0 && !database.is_initialised() {
+            return Err(failure::err_msg(format!(
+                r#"The database is not initialised with Warden. Error trying to deploy migration "{}". The initial migration must be deployed first"#,
+                meta.get_identity()
+            )));
+        }
+
+        if let Some(migration) = database.get_migration(id) {
+            if migration.deployed_at.is_some() {
+                Err(failure::err_msg(format!(
+                    "Migration {} has already been deployed",
+                    meta.get_identity()
+                )))?
+            }
+        }
+
+        if let Some(error) = database.get_failure(id).map(str::to_string) {
+            if meta.get_transaction() != TransactionMode::None {
+                return Err(failure::err_msg(format!(
+                    r#"Migration "{}": {}"#,
+                    meta.get_identity(),
+                    error
+                )));
+            }
+
+            register(database, meta, source)?;
+
+            if let Some(migration) = database.get_migration_mut(id) {
+                migration.partially_applied_at = Some(now());
+            }
+
+            log(
+                database,
+                &format!("Partially applied | {} | {}", meta.get_identity(), error),
+            );
+
+            return Err(failure::err_msg(format!(
+                r#"Migration "{}" has been partially applied, {}"#,
+                meta.get_identity(),
+                error
+            )));
+        }
+
+        register(database, meta, source)?;
+
+        if let Some(migration) = database.get_migration_mut(id) {
+            migration.deployed_at = Some(now());
+            migration.partially_applied_at = None;
+        }
+
+        log(database, &format!("Deployed | {}", meta.get_identity()));
+
+        Ok(())
+    }
+}
+
+/// Open the in-memory database of the URL, `mock://<name>` optionally followed by
+/// `?fail=<uid>,<uid>` to make the deployments of the migrations fail.
+/// The registry schema makes no difference, since every database has a registry of its own
+pub fn open(url: &str, _schema: &str) -> Result<Connection, Error> {
+    let rest = url
+        .strip_prefix("mock://")
+        .or_else(|| url.strip_prefix("mock:"))
+        .ok_or_else(|| {
+            failure::err_msg(format!(
+                "Invalid mock database URL \"{}\", expected mock://<name>",
+                url
+            ))
+        })?;
+
+    let (name, query) = match rest.find('?') {
+        Some(pos) => (&rest[..pos], &rest[pos + 1..]),
+        None => (rest, ""),
+    };
+
+    let database = database::get(name);
+
+    for param in query.split('&').filter(|param| !param.is_empty()) {
+        let (key, value) = match param.find('=') {
+            Some(pos) => (&param[..pos], &param[pos + 1..]),
+            None => (param, ""),
+        };
+
+        match key {
+            "fail" => {
+                for uid in value.split(',').filter(|uid| !uid.is_empty()) {
+                    let id = base36::decode(uid).ok_or_else(|| {
+                        failure::err_msg(format!(
+                            "Invalid migration uid \"{}\" in the mock database URL",
+                            uid
+                        ))
+                    })?;
+
+                    database.lock().unwrap().fail_on(id, SCRIPTED_FAILURE);
+                }
+            }
+            _ => Err(failure::err_msg(format!(
+                "Unknown parameter \"{}\" of the mock database URL",
+                key
+            )))?,
+        }
+    }
+
+    Ok(Connection {
+        id: CONNECTIONS.fetch_add(1, Ordering::SeqCst),
+        catalog: name.to_string(),
+        database: database,
+    })
+}
+
+fn register(database: &mut Database, meta: &Meta, source: &str) -> Result<(), Error> {
+    let snapshot = snapshot::Snapshot::take(snapshot::Format::TarGz, meta)?;
+    let seal = meta.get_seal_meta().read_the_seal()?;
+
+    let revert_source = if let Some(revert) = meta.get_revert() {
+        Some(FileRead::read(revert.get_target())?.read_string()?)
+    } else {
+        None
+    };
+
+    let id = get_id(meta)?;
+
+    // a partially applied migration stays so until it is completed
+    let partially_applied_at = database
+        .get_migration(id)
+        .and_then(|migration| migration.partially_applied_at);
+
+    database.register(
+        id,
+        Migration {
+            registration: Registration {
+                identity: meta.get_identity().clone(),
+                source: source.to_string(),
+                seal: seal,
+            },
+            revert_source: revert_source,
+            snapshot_format: snapshot.format.as_str().to_string(),
+            snapshot_data: snapshot.data,
+            registered_at: now(),
+            deployed_at: None,
+            partially_applied_at: partially_applied_at,
+        },
+    );
+
+    log(database, &format!("Registered | {}", meta.get_identity()));
+
+    Ok(())
+}
+
+fn log(database: &mut Database, action: &str) {
+    let user = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_default();
+
+    let entry = ActionLogEntry {
+        id: database.get_action_log().len() as i64 + 1,
+        ts: now(),
+        user: user,
+        client_address: None,
+        session_pid: process::id() as i32,
+        action: action.to_string(),
+    };
+
+    database.log(entry);
+}
+
+fn get_id(meta: &Meta) -> Result<u128, Error> {
+    meta.get_identity()
+        .get_id()
+        .ok_or(failure::err_msg("could not decode migration id"))
+}
+
+fn read_source(meta: &Meta) -> Result<String, Error> {
+    Ok(FileRead::read(meta.get_target())?.read_string()?)
+}
+
+fn now() -> DateTime<FixedOffset> {
+    Utc::now().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::Mock;
+    use path_abs::PathFile;
+    use warden_core::dbms::driver::Driver;
+    use warden_core::dbms::Connection as _;
+    use warden_core::sewer::Sewer;
+    use warden_core::testing;
+
+    const PATCH: &str = "create table test (id int);";
+
+    #[test]
+    fn test_deploy() {
+        let folder = testing::temp_dir("mock-deploy");
+        Mock.create_initial_migration(&folder).unwrap();
+
+        let meta = Meta::open(PathFile::new(folder.join("000000--warden-init/meta.yml")).unwrap()).unwrap();
+
+        let connection = open("mock://test_deploy?fail=000001", "warden").unwrap();
+        connection.deploy(meta.clone(), &Timeouts::default()).unwrap();
+        assert!(connection.deploy(meta, &Timeouts::default()).is_err());
+
+        let failing = testing::create_migration(&folder, "000001--failing", Some(PATCH));
+        let error = connection.deploy(failing, &Timeouts::default()).unwrap_err();
+
+        assert!(error.to_string().contains(SCRIPTED_FAILURE));
+        assert_eq!(connection.get_last_deployed_migration().unwrap(), Some(0));
+        assert!(!connection.get_deployed_migrations().unwrap().contains(&1));
+        assert!(connection.get_registered_migration(1).unwrap().is_none());
+
+        folder.remove_all().unwrap();
+
+        let database = database::get("test_deploy");
+        let database = database.lock().unwrap();
+
+        assert!(database.is_initialised());
+        assert!(database.get_migration(1).is_none());
+
+        let deployments = database.get_deployments();
+        assert_eq!(deployments.len(), 3);
+        assert!(deployments[0].error.is_none());
+        assert!(deployments[1].error.is_some());
+        assert_eq!(deployments[2].meta.get_identity().get_id(), Some(1));
+        assert!(deployments[2].error.as_ref().unwrap().contains(SCRIPTED_FAILURE));
+    }
+
+    #[test]
+    fn test_deploy_atomic() {
+        let folder = testing::temp_dir("mock-deploy-atomic");
+        Mock.create_initial_migration(&folder).unwrap();
+
+        let init = Meta::open(PathFile::new(folder.join("000000--warden-init/meta.yml")).unwrap()).unwrap();
+        let migrations = vec![
+            testing::create_migration(&folder, "000001--first", Some(PATCH)),
+            testing::create_migration(&folder, "000002--second", Some(PATCH)),
+            testing::create_migration(&folder, "000003--third", Some(PATCH)),
+        ];
+
+        let connection = open("mock://test_deploy_atomic?fail=000002", "warden").unwrap();
+        connection.deploy(init, &Timeouts::default()).unwrap();
+
+        let (failed, error) = connection
+            .deploy_atomic(migrations, &Timeouts::default())
+            .unwrap_err();
+
+        assert_eq!(failed, 1);
+        assert!(error.to_string().contains(SCRIPTED_FAILURE));
+        assert_eq!(connection.get_last_deployed_migration().unwrap(), Some(0));
+
+        folder.remove_all().unwrap();
+
+        let database = database::get("test_deploy_atomic");
+        let database = database.lock().unwrap();
+
+        // the third one has never been attempted
+        let deployments = &database.get_deployments()[1..];
+        assert_eq!(deployments.len(), 2);
+        assert!(deployments.iter().all(|deployment| deployment.atomic));
+        assert!(deployments[0].rolled_back && deployments[0].error.is_none());
+        assert!(!deployments[1].rolled_back && deployments[1].error.is_some());
+        assert!(database.get_migration(1).is_none());
+    }
+
+    #[test]
+    fn test_revert_empty() {
+        let folder = testing::temp_dir("mock-revert-empty");
+        Mock.create_initial_migration(&folder).unwrap();
+
+        let init = Meta::open(PathFile::new(folder.join("000000--warden-init/meta.yml")).unwrap()).unwrap();
+        let meta = testing::create_migration_with_revert(&folder, "000001--empty-revert", Some(PATCH), None);
+
+        // the revert tree has no patches, so there is nothing to build and seal
+        assert!(meta.get_revert().is_none());
+        assert!(Sewer::revert(meta.clone()).unwrap().is_none());
+
+        let connection = open("mock://test_revert_empty", "warden").unwrap();
+        connection.deploy(init, &Timeouts::default()).unwrap();
+        connection.deploy(meta.clone(), &Timeouts::default()).unwrap();
+
+        let result = connection.revert(meta);
+
+        folder.remove_all().unwrap();
+
+        assert!(result.unwrap_err().to_string().contains("has no revert source"));
+        assert!(connection.get_deployed_migrations().unwrap().contains(&1));
+    }
+}
@@ -0,0 +1,141 @@
+//! The in-memory databases of the mock driver.
+//!
+//! A database lives as long as the process does and is shared by all the
+//! connections opened to the same URL, so that a test can deploy through
+//! the driver and then inspect what has been deployed, or script failures
+//! beforehand.
+
+use chrono::prelude::{DateTime, FixedOffset};
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use warden_core::dbms::{ActionLogEntry, Registration, Timeouts};
+use warden_core::migration::meta::Meta;
+
+lazy_static! {
+    static ref DATABASES: Mutex<HashMap<String, Arc<Mutex<Database>>>> = Mutex::new(HashMap::new());
+}
+
+/// The database of the name (`mock://<name>`), created empty on the first use
+pub fn get(name: &str) -> Arc<Mutex<Database>> {
+    DATABASES
+        .lock()
+        .unwrap()
+        .entry(name.to_string())
+        .or_insert_with(|| Arc::new(Mutex::new(Database::default())))
+        .clone()
+}
+
+/// Forget the database, so that the next connection starts from scratch
+pub fn remove(name: &str) {
+    DATABASES.lock().unwrap().remove(name);
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    migrations: BTreeMap<u128, Migration>,
+    deployments: Vec<Deployment>,
+    action_log: Vec<ActionLogEntry>,
+
+    /// The errors to fail the deployments of the migrations with
+    failures: BTreeMap<u128, String>,
+
+    /// The connection holding the lock
+    pub(crate) lock_holder: Option<u64>,
+}
+
+/// A migration registered in the database
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub registration: Registration,
+    pub revert_source: Option<String>,
+
+    pub snapshot_format: String,
+    pub snapshot_data: Vec<u8>,
+
+    pub registered_at: DateTime<FixedOffset>,
+
+    /// None if the migration has not been deployed
+    pub deployed_at: Option<DateTime<FixedOffset>>,
+
+    /// The time of the failed deployment outside of a transaction (if any)
+    pub partially_applied_at: Option<DateTime<FixedOffset>>,
+}
+
+/// A deployment of a migration, successful or not
+#[derive(Debug, Clone)]
+pub struct Deployment {
+    pub meta: Meta,
+
+    /// The built migration SQL
+    pub source: String,
+
+    pub timeouts: Timeouts,
+
+    /// Whether the migration has been deployed along with others in a single transaction
+    pub atomic: bool,
+
+    /// Whether the deployment has been rolled back because of a failure of another migration
+    pub rolled_back: bool,
+
+    /// None if the migration has been deployed
+    pub error: Option<String>,
+}
+
+impl Database {
+    /// Make the deployments of the migration fail with the error
+    pub fn fail_on(&mut self, id: u128, error: &str) {
+        self.failures.insert(id, error.to_string());
+    }
+
+    pub fn get_failure(&self, id: u128) -> Option<&str> {
+        self.failures.get(&id).map(String::as_str)
+    }
+
+    /// All the deployments in the order they happened
+    pub fn get_deployments(&self) -> &[Deployment] {
+        &self.deployments
+    }
+
+    pub fn get_migrations(&self) -> &BTreeMap<u128, Migration> {
+        &self.migrations
+    }
+
+    pub fn get_migration(&self, id: u128) -> Option<&Migration> {
+        self.migrations.get(&id)
+    }
+
+    pub fn get_action_log(&self) -> &[ActionLogEntry] {
+        &self.action_log
+    }
+
+    /// Whether the initial migration has been deployed
+    pub fn is_initialised(&self) -> bool {
+        self.migrations
+            .get(&0)
+            .map(|migration| migration.deployed_at.is_some())
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn get_migration_mut(&mut self, id: u128) -> Option<&mut Migration> {
+        self.migrations.get_mut(&id)
+    }
+
+    pub(crate) fn register(&mut self, id: u128, migration: Migration) {
+        self.migrations.insert(id, migration);
+    }
+
+    pub(crate) fn record(&mut self, deployment: Deployment) {
+        self.deployments.push(deployment);
+    }
+
+    pub(crate) fn log(&mut self, entry: ActionLogEntry) {
+        self.action_log.push(entry);
+    }
+
+    /// Drop the changes made since the state was cloned, keeping the deployments recorded
+    pub(crate) fn rollback(&mut self, state: Database) {
+        self.migrations = state.migrations;
+        self.action_log = state.action_log;
+    }
+}
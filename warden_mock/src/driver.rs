@@ -0,0 +1,39 @@
+use failure::Error;
+use path_abs::PathDir;
+use warden_core::dbms::driver::{self, Driver, DriverFactory};
+use warden_core::dbms::Connection;
+
+static NAME: &'static str = "mock";
+
+static INIT_SOURCE: &'static str = "-- The mock driver keeps the warden registry in memory,
+-- so there is nothing to be created in the database
+";
+
+pub struct Mock;
+
+impl Driver for Mock {
+    fn name(&self) -> &'static str { NAME }
+
+    fn create_initial_migration(&self, folder: &PathDir) -> Result<(), Error> {
+        driver::create_initial_migration(folder, INIT_SOURCE)
+    }
+
+    fn open_connection(&self, url: &str, registry_schema: &str) -> Result<Box<Connection>, Error> {
+        Ok(Box::new(super::connection::open(url, registry_schema)?))
+    }
+}
+
+
+pub struct Factory;
+
+impl DriverFactory for Factory {
+    fn name(&self) -> &'static str { NAME }
+
+    fn new(&self) -> Box<Driver> {
+        Box::new(Mock)
+    }
+}
+
+pub fn register_driver() {
+    warden_core::dbms::driver::register_driver(Box::new(Factory));
+}
@@ -0,0 +1,3 @@
+pub mod connection;
+pub mod database;
+pub mod driver;
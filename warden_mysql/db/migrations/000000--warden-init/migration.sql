@@ -0,0 +1,104 @@
+-- ---
+-- version: 0.1
+-- ...
+-- timestamp: !!timestamp 2026-10-18T08:07:57.994+00:00
+-- manifest:
+--   - sql/warden.sql
+--   - sql/warden/action_log.sql
+--   - sql/warden/migration.sql
+--   - sql/warden/migration/revert.sql
+--   - sql/warden/migration/seal.sql
+--   - sql/warden/migration/snapshot.sql
+--   - sql/warden/migration/source.sql
+--   - sql/warden/schema_version.sql
+-- ...
+
+-- BEGIN: sql/warden.sql
+
+create database warden character set utf8mb4 collate utf8mb4_bin;
+
+-- END: sql/warden.sql
+
+-- BEGIN: sql/warden/action_log.sql
+
+create table warden.action_log (
+  id bigint not null auto_increment primary key,
+  ts timestamp(3) not null default current_timestamp(3),
+  user varchar(255) not null,
+  client_address varchar(255) null,
+  session_pid bigint not null comment 'The connection ID',
+  the_action text
+) engine = InnoDB comment 'The log of the actions performed by warden';
+
+-- END: sql/warden/action_log.sql
+
+-- BEGIN: sql/warden/migration.sql
+
+create table warden.migration (
+  id bigint not null primary key comment 'Migration ID',
+  name varchar(255) not null comment 'Migration name',
+  sync_ts timestamp(3) not null default current_timestamp(3) comment 'Registration timestamp',
+  deploy_ts timestamp(3) null default null comment 'Migration deployment timestamp (null if it hasn''t been deployed)',
+  partial_ts timestamp(3) null default null comment 'Timestamp of a failed deployment committed implicitly (the migration may be partially applied)'
+) engine = InnoDB comment 'Registered migrations';
+
+-- END: sql/warden/migration.sql
+
+-- BEGIN: sql/warden/migration/revert.sql
+
+create table warden.migration_revert (
+  migration_id bigint not null primary key,
+  data longtext comment 'The SQL to be executed to revert the migration',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration revert source (SQL)';
+
+-- END: sql/warden/migration/revert.sql
+
+-- BEGIN: sql/warden/migration/seal.sql
+
+create table warden.migration_seal (
+  migration_id bigint not null primary key,
+  generated_at varchar(64) not null comment 'The seal generation timestamp (RFC 3339)',
+  algo varchar(16) not null comment 'Seal calculation algorithm',
+  value blob comment 'Seal value. Signature generated according to the algorithm',
+  revert_value blob null comment 'Seal value of the revert source (null if the migration has no revert)',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration seal (signature)';
+
+-- END: sql/warden/migration/seal.sql
+
+-- BEGIN: sql/warden/migration/snapshot.sql
+
+create table warden.migration_snapshot (
+  migration_id bigint not null primary key,
+  format varchar(32) not null comment 'Datum format (e.g. .tar.gz)',
+  data longblob comment 'The snapshot data',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration snapshots';
+
+-- END: sql/warden/migration/snapshot.sql
+
+-- BEGIN: sql/warden/migration/source.sql
+
+create table warden.migration_source (
+  migration_id bigint not null primary key,
+  data longtext comment 'The actual migration SQL to be executed',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration source (SQL)';
+
+-- END: sql/warden/migration/source.sql
+
+-- BEGIN: sql/warden/schema_version.sql
+
+create table warden.schema_version (
+  version int not null primary key comment 'The internal schema version',
+  upgrade_ts timestamp(3) not null default current_timestamp(3) comment 'When the schema has been upgraded to the version'
+) engine = InnoDB comment 'Versions of the warden schema (the highest one is the current)';
+
+insert into warden.schema_version (version) values (1);
+
+-- END: sql/warden/schema_version.sql
\ No newline at end of file
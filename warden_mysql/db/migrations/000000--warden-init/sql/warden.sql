@@ -0,0 +1 @@
+create database warden character set utf8mb4 collate utf8mb4_bin;
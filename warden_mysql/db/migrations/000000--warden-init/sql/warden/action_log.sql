@@ -0,0 +1,8 @@
+create table warden.action_log (
+  id bigint not null auto_increment primary key,
+  ts timestamp(3) not null default current_timestamp(3),
+  user varchar(255) not null,
+  client_address varchar(255) null,
+  session_pid bigint not null comment 'The connection ID',
+  the_action text
+) engine = InnoDB comment 'The log of the actions performed by warden';
@@ -0,0 +1,7 @@
+create table warden.migration (
+  id bigint not null primary key comment 'Migration ID',
+  name varchar(255) not null comment 'Migration name',
+  sync_ts timestamp(3) not null default current_timestamp(3) comment 'Registration timestamp',
+  deploy_ts timestamp(3) null default null comment 'Migration deployment timestamp (null if it hasn''t been deployed)',
+  partial_ts timestamp(3) null default null comment 'Timestamp of a failed deployment committed implicitly (the migration may be partially applied)'
+) engine = InnoDB comment 'Registered migrations';
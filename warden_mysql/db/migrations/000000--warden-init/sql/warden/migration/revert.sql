@@ -0,0 +1,6 @@
+create table warden.migration_revert (
+  migration_id bigint not null primary key,
+  data longtext comment 'The SQL to be executed to revert the migration',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration revert source (SQL)';
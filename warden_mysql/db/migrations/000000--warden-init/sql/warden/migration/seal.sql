@@ -0,0 +1,9 @@
+create table warden.migration_seal (
+  migration_id bigint not null primary key,
+  generated_at varchar(64) not null comment 'The seal generation timestamp (RFC 3339)',
+  algo varchar(16) not null comment 'Seal calculation algorithm',
+  value blob comment 'Seal value. Signature generated according to the algorithm',
+  revert_value blob null comment 'Seal value of the revert source (null if the migration has no revert)',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration seal (signature)';
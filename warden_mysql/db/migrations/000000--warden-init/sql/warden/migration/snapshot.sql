@@ -0,0 +1,7 @@
+create table warden.migration_snapshot (
+  migration_id bigint not null primary key,
+  format varchar(32) not null comment 'Datum format (e.g. .tar.gz)',
+  data longblob comment 'The snapshot data',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration snapshots';
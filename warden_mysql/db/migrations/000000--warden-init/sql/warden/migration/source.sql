@@ -0,0 +1,6 @@
+create table warden.migration_source (
+  migration_id bigint not null primary key,
+  data longtext comment 'The actual migration SQL to be executed',
+
+  foreign key (migration_id) references warden.migration (id) on delete cascade on update cascade
+) engine = InnoDB comment 'Migration source (SQL)';
@@ -0,0 +1,6 @@
+create table warden.schema_version (
+  version int not null primary key comment 'The internal schema version',
+  upgrade_ts timestamp(3) not null default current_timestamp(3) comment 'When the schema has been upgraded to the version'
+) engine = InnoDB comment 'Versions of the warden schema (the highest one is the current)';
+
+insert into warden.schema_version (version) values (1);
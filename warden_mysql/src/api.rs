@@ -0,0 +1,211 @@
+//! The counterparts of the warden API functions of the Postgres driver.
+//! They are implemented here rather than as stored procedures, since
+//! those cannot execute the migrations (dynamic SQL is limited in MySQL).
+
+use chrono::prelude::{DateTime, FixedOffset};
+use failure::{Error, ResultExt};
+use mysql::prelude::Queryable;
+use mysql::Conn;
+use warden_core::migration::base36;
+
+use crate::registry::render;
+use crate::statement;
+
+pub fn do_register_migration(
+    connection: &mut Conn,
+    schema: &str,
+    id: u128,
+    name: &str,
+    source: &str,
+    snapshot_format: &str,
+    snapshot_data: &[u8],
+    seal_generated_at: &DateTime<FixedOffset>,
+    seal_algo: &str,
+    seal_data: &[u8],
+    revert_source: Option<&str>,
+    seal_revert_data: Option<&[u8]>,
+) -> Result<(), Error> {
+    let fullname = fullname(id, name);
+
+    let registered: Option<(bool, Option<String>)> = connection.exec_first(
+        &*render(
+            "select deploy_ts is not null, partial_ts from warden.migration where id = ?",
+            schema,
+        ),
+        (id as i64,),
+    )?;
+
+    // a partially applied migration stays so until it is completed
+    let partial_ts = match registered {
+        Some((true, _)) => Err(failure::err_msg(format!(
+            "Migration {} has already been deployed",
+            fullname
+        )))?,
+        Some((false, partial_ts)) => {
+            // the migration has been reverted (or partially applied), so we register it anew
+            do_unregister_migration(connection, schema, id)?;
+            partial_ts
+        }
+        None => None,
+    };
+
+    connection.exec_drop(
+        &*render("insert into warden.migration (id, name, partial_ts) values (?, ?, ?)", schema),
+        (id as i64, name, partial_ts),
+    )?;
+    connection.exec_drop(
+        &*render("insert into warden.migration_source (migration_id, data) values (?, ?)", schema),
+        (id as i64, source),
+    )?;
+    connection.exec_drop(
+        &*render(
+            "insert into warden.migration_snapshot (migration_id, format, data) values (?, ?, ?)",
+            schema,
+        ),
+        (id as i64, snapshot_format, snapshot_data),
+    )?;
+    connection.exec_drop(
+        &*render(
+            "insert into warden.migration_seal (migration_id, generated_at, algo, value, revert_value)
+            values (?, ?, ?, ?, ?)",
+            schema,
+        ),
+        (
+            id as i64,
+            seal_generated_at.to_rfc3339(),
+            seal_algo,
+            seal_data,
+            seal_revert_data,
+        ),
+    )?;
+
+    if let Some(revert_source) = revert_source {
+        connection.exec_drop(
+            &*render("insert into warden.migration_revert (migration_id, data) values (?, ?)", schema),
+            (id as i64, revert_source),
+        )?;
+    }
+
+    do_log(connection, schema, &format!("Registered | {}", fullname))
+}
+
+/// Remove the registration of a migration that has not been deployed
+pub fn do_unregister_migration(connection: &mut Conn, schema: &str, id: u128) -> Result<(), Error> {
+    let (fullname, _) = get_migration(connection, schema, id)?;
+
+    connection.exec_drop(
+        &*render("delete from warden.migration where id = ? and deploy_ts is null", schema),
+        (id as i64,),
+    )?;
+
+    do_log(connection, schema, &format!("Unregistered | {}", fullname))
+}
+
+pub fn do_complete_migration(connection: &mut Conn, schema: &str, id: u128) -> Result<(), Error> {
+    let (fullname, deployed) = get_migration(connection, schema, id)?;
+
+    if deployed {
+        Err(failure::err_msg(format!(
+            "Migration {} has already been deployed",
+            fullname
+        )))?
+    }
+
+    connection.exec_drop(
+        &*render(
+            "update warden.migration set deploy_ts = current_timestamp(3), partial_ts = null where id = ?",
+            schema,
+        ),
+        (id as i64,),
+    )?;
+
+    do_log(connection, schema, &format!("Deployed | {}", fullname))
+}
+
+pub fn do_fail_migration(connection: &mut Conn, schema: &str, id: u128, error: &str) -> Result<(), Error> {
+    let (fullname, _) = get_migration(connection, schema, id)?;
+
+    connection.exec_drop(
+        &*render(
+            "update warden.migration set partial_ts = current_timestamp(3) where id = ?",
+            schema,
+        ),
+        (id as i64,),
+    )?;
+
+    do_log(
+        connection,
+        schema,
+        &format!("Partially applied | {} | {}", fullname, error),
+    )
+}
+
+/// Execute the registered revert source and mark the migration as not deployed
+pub fn do_revert_migration(connection: &mut Conn, schema: &str, id: u128) -> Result<(), Error> {
+    let (fullname, deployed) = get_migration(connection, schema, id)?;
+
+    if !deployed {
+        Err(failure::err_msg(format!(
+            "Migration {} has not been deployed",
+            fullname
+        )))?
+    }
+
+    let revert: Option<Option<String>> = connection.exec_first(
+        &*render("select data from warden.migration_revert where migration_id = ?", schema),
+        (id as i64,),
+    )?;
+
+    let revert = revert.and_then(|revert| revert).ok_or_else(|| {
+        failure::err_msg(format!("Migration {} has no revert source", fullname))
+    })?;
+
+    for (num, sql) in statement::split(&revert).into_iter().enumerate() {
+        connection.query_drop(sql).context(format!(
+            "Reverting migration {}, {}",
+            fullname,
+            statement::describe(&revert, sql, num + 1)
+        ))?;
+    }
+
+    connection.exec_drop(
+        &*render("update warden.migration set deploy_ts = null where id = ?", schema),
+        (id as i64,),
+    )?;
+
+    do_log(connection, schema, &format!("Reverted | {}", fullname))
+}
+
+pub fn do_log(connection: &mut Conn, schema: &str, action: &str) -> Result<(), Error> {
+    connection.exec_drop(
+        &*render(
+            "insert into warden.action_log (user, client_address, session_pid, the_action)
+            values (current_user(), nullif(substring_index(user(), '@', -1), 'localhost'), connection_id(), ?)",
+            schema,
+        ),
+        (action,),
+    )?;
+
+    Ok(())
+}
+
+/// The full name of a registered migration and whether it is deployed
+fn get_migration(connection: &mut Conn, schema: &str, id: u128) -> Result<(String, bool), Error> {
+    let migration: Option<(String, bool)> = connection.exec_first(
+        &*render("select name, deploy_ts is not null from warden.migration where id = ?", schema),
+        (id as i64,),
+    )?;
+
+    let (name, deployed) = migration
+        .ok_or_else(|| failure::err_msg(format!("Unknown migration with \"{}\"", uid(id))))?;
+
+    Ok((fullname(id, &name), deployed))
+}
+
+pub fn uid(id: u128) -> String {
+    format!("{:0>6}", base36::encode(id))
+}
+
+fn fullname(id: u128, name: &str) -> String {
+    format!("{}--{}", uid(id), name)
+}
@@ -0,0 +1,53 @@
+use failure::Error;
+use libflate::gzip::Decoder;
+use path_abs::PathDir;
+use tar;
+use warden_core::dbms::driver::{Driver, DriverFactory};
+use warden_core::dbms::{Capabilities, Connection};
+
+static NAME: &'static str = "mysql";
+
+pub struct MySQL;
+
+impl Driver for MySQL {
+    fn name(&self) -> &'static str { NAME }
+
+    /// DDL statements commit implicitly
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            transactional_ddl: false,
+            ..Capabilities::default()
+        }
+    }
+
+    /// The server is not listening yet
+    fn is_transient_error(&self, error: &Error) -> bool {
+        matches!(
+            error.downcast_ref::<mysql::Error>(),
+            Some(mysql::Error::IoError(_)) | Some(mysql::Error::DriverError(mysql::DriverError::CouldNotConnect(_)))
+        )
+    }
+
+    fn create_initial_migration(&self, folder: &PathDir) -> Result<(), Error> {
+        Ok(tar::Archive::new(Decoder::new(crate::zero_migration_tar())?).unpack(folder)?)
+    }
+
+    fn open_connection(&self, url: &str, registry_schema: &str) -> Result<Box<Connection>, Error> {
+        Ok(Box::new(super::connection::open(url, registry_schema)?))
+    }
+}
+
+
+pub struct Factory;
+
+impl DriverFactory for Factory {
+    fn name(&self) -> &'static str { NAME }
+
+    fn new(&self) -> Box<Driver> {
+        Box::new(MySQL)
+    }
+}
+
+pub fn register_driver() {
+    warden_core::dbms::driver::register_driver(Box::new(Factory));
+}
@@ -0,0 +1,89 @@
+//! Named lock (`GET_LOCK`) guarding the warden schema
+//! from concurrent modifications. It belongs to the session,
+//! so the server releases it along with the connection.
+
+use failure::Error;
+use mysql::prelude::Queryable;
+use mysql::Conn;
+use std::time::Duration;
+
+/// How long to wait in a single `GET_LOCK` call when waiting indefinitely
+const WAIT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Acquire the lock keyed on the schema.
+/// Waits indefinitely when no timeout given, otherwise gives up once
+/// the timeout expires (zero timeout means to not wait at all)
+pub fn acquire(connection: &mut Conn, schema: &str, timeout: Option<Duration>) -> Result<(), Error> {
+    if try_acquire(connection, schema, Duration::from_secs(0))? {
+        return Ok(());
+    }
+
+    let holder = describe_holder(connection, schema)?;
+
+    if let Some(timeout) = timeout {
+        if timeout > Duration::from_secs(0) {
+            log::info!("Waiting for the lock held by {}", holder);
+
+            if try_acquire(connection, schema, timeout)? {
+                return Ok(());
+            }
+        }
+    } else {
+        log::info!("Waiting for the lock held by {}", holder);
+
+        while !try_acquire(connection, schema, WAIT_INTERVAL)? {}
+
+        return Ok(());
+    }
+
+    Err(failure::err_msg(format!(
+        "Could not acquire the lock on the \"{}\" schema, it is held by {}",
+        schema,
+        describe_holder(connection, schema)?
+    )))
+}
+
+pub fn release(connection: &mut Conn, schema: &str) -> Result<(), Error> {
+    connection.exec_drop("select release_lock(?)", (name(schema),))?;
+
+    Ok(())
+}
+
+/// The lock names are limited to 64 characters, hence the limit of the schema name
+fn name(schema: &str) -> String {
+    format!("warden:{}", schema)
+}
+
+fn try_acquire(connection: &mut Conn, schema: &str, timeout: Duration) -> Result<bool, Error> {
+    let result: Option<Option<i32>> = connection.exec_first(
+        "select get_lock(?, ?)",
+        (name(schema), timeout.as_secs_f64()),
+    )?;
+
+    match result {
+        Some(Some(result)) => Ok(result == 1),
+        _ => Err(failure::err_msg("Could not acquire the lock")),
+    }
+}
+
+/// Human readable description of the session holding the lock
+fn describe_holder(connection: &mut Conn, schema: &str) -> Result<String, Error> {
+    let row: Option<(u64, String, String)> = connection.exec_first(
+        "select
+          p.id,
+          coalesce(p.user, ''),
+          coalesce(p.host, 'local')
+        from
+          information_schema.processlist p
+        where
+          p.id = is_used_lock(?)
+        ",
+        (name(schema),),
+    )?;
+
+    Ok(if let Some((id, user, host)) = row {
+        format!("connection id {} (user \"{}\", client {})", id, user, host)
+    } else {
+        "an unknown connection".to_string()
+    })
+}
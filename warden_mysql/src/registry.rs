@@ -0,0 +1,80 @@
+//! Rendering the warden SQL against the configured registry schema.
+//!
+//! The schema is a database in terms of MySQL. The initial migration and
+//! the API calls are written against the `warden` one, which gets replaced
+//! with the configured one right before the SQL is sent to the server.
+
+use std::borrow::Cow;
+
+/// The schema name the SQL is written against
+const CANONICAL: &str = "warden";
+
+/// Replace the canonical schema name with the given one.
+/// Only the schema qualified names (`warden.name`) and the
+/// schema definitions (`schema warden` or `database warden`) are replaced.
+pub fn render<'a>(sql: &'a str, schema: &str) -> Cow<'a, str> {
+    if schema == CANONICAL {
+        return Cow::Borrowed(sql);
+    }
+
+    let bytes = sql.as_bytes();
+    let mut result = String::with_capacity(sql.len());
+    let mut start = 0;
+
+    for (pos, _) in sql.match_indices(CANONICAL) {
+        let end = pos + CANONICAL.len();
+
+        if pos > 0 && is_ident_char(bytes[pos - 1]) {
+            continue;
+        }
+
+        let qualifies = bytes.get(end) == Some(&b'.');
+        let defines = (sql[..pos].ends_with("schema ") || sql[..pos].ends_with("database "))
+            && bytes.get(end).map(|c| !is_ident_char(*c)).unwrap_or(true);
+
+        if qualifies || defines {
+            result.push_str(&sql[start..pos]);
+            result.push_str(schema);
+            start = end;
+        }
+    }
+
+    if start == 0 {
+        return Cow::Borrowed(sql);
+    }
+
+    result.push_str(&sql[start..]);
+
+    Cow::Owned(result)
+}
+
+/// Whether the name can be used as the schema name without quoting.
+/// It is also a part of the lock name, which is limited to 64 characters
+pub fn is_valid_schema(name: &str) -> bool {
+    let bytes = name.as_bytes();
+
+    !bytes.is_empty()
+        && bytes.len() <= 57
+        && !bytes[0].is_ascii_digit()
+        && bytes.iter().all(|c| is_ident_char(*c) && !c.is_ascii_uppercase())
+}
+
+fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render(
+                "create database warden;\ninsert into warden.migration (id) select id from my_warden.x;\n",
+                "app_registry"
+            ),
+            "create database app_registry;\ninsert into app_registry.migration (id) select id from my_warden.x;\n"
+        );
+    }
+}
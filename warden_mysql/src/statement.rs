@@ -0,0 +1,234 @@
+//! Splitting SQL scripts into separate statements, so that they can be
+//! executed one by one, and telling which of them commit implicitly
+
+/// Split the SQL into statements by the delimiter, taking into account
+/// string literals, quoted identifiers and comments.
+/// The `DELIMITER` client command is supported (and left out), so that
+/// the stored programs can be defined the way they are in the mysql client.
+/// Statements consisting of comments only are left out.
+pub fn split(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut result = Vec::new();
+
+    let mut delimiter = ";";
+    let mut start = 0;
+    let mut pos = 0;
+
+    // whether there has been nothing but whitespaces since the statement start
+    let mut blank = true;
+
+    while pos < bytes.len() {
+        if blank && starts_with_keyword(&sql[pos..], "delimiter") {
+            push_statement(&mut result, &sql[start..pos]);
+
+            let line_end = skip_line_comment(bytes, pos);
+            let value = sql[pos + "delimiter".len()..line_end].trim();
+
+            if !value.is_empty() {
+                delimiter = value;
+            }
+
+            start = line_end;
+            pos = line_end;
+            continue;
+        }
+
+        if bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        blank = false;
+
+        pos = match bytes[pos] {
+            b'\'' | b'"' | b'`' => skip_quoted(bytes, pos, bytes[pos]),
+            b'#' => skip_line_comment(bytes, pos),
+            b'-' if is_line_comment(bytes, pos) => skip_line_comment(bytes, pos),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => skip_block_comment(bytes, pos),
+            _ if bytes[pos..].starts_with(delimiter.as_bytes()) => {
+                push_statement(&mut result, &sql[start..pos]);
+                start = pos + delimiter.len();
+                blank = true;
+                start
+            }
+            _ => pos + 1,
+        };
+    }
+
+    if start < bytes.len() {
+        push_statement(&mut result, &sql[start..]);
+    }
+
+    result
+}
+
+/// Human readable position of the statement (a slice of the source)
+/// such as "statement 2 (line 14)"
+pub fn describe(source: &str, statement: &str, number: usize) -> String {
+    let offset = statement.as_ptr() as usize - source.as_ptr() as usize;
+    let line = source[..offset].matches('\n').count() + 1;
+
+    format!("statement {} (line {})", number, line)
+}
+
+/// Whether the statement commits the current transaction implicitly
+/// (mostly DDL, see "Statements That Cause an Implicit Commit" in the MySQL manual)
+pub fn causes_implicit_commit(statement: &str) -> bool {
+    let words: Vec<String> = strip_comments(statement)
+        .split_whitespace()
+        .take(3)
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+
+    let first = words.first().map(String::as_str).unwrap_or("");
+    let second = words.get(1).map(String::as_str).unwrap_or("");
+
+    match first {
+        // temporary tables are the exception
+        "create" | "drop" => second != "temporary",
+        "alter" | "rename" | "truncate" | "grant" | "revoke" | "install" | "uninstall" => true,
+        "analyze" | "cache" | "check" | "flush" | "optimize" | "repair" | "reset" => true,
+        "begin" | "commit" | "start" | "lock" | "unlock" => true,
+        "load" => second == "index",
+        "set" => second == "password",
+        _ => false,
+    }
+}
+
+fn push_statement<'a>(result: &mut Vec<&'a str>, statement: &'a str) {
+    let statement = statement.trim();
+
+    if !strip_comments(statement).trim().is_empty() {
+        result.push(statement);
+    }
+}
+
+/// The statement without the comments (the executable comments `/*! ... */` included)
+fn strip_comments(statement: &str) -> String {
+    let bytes = statement.as_bytes();
+    let mut result = String::with_capacity(statement.len());
+    let mut copied = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let comment_end = match bytes[pos] {
+            b'\'' | b'"' | b'`' => {
+                pos = skip_quoted(bytes, pos, bytes[pos]);
+                continue;
+            }
+            b'#' => skip_line_comment(bytes, pos),
+            b'-' if is_line_comment(bytes, pos) => skip_line_comment(bytes, pos),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => skip_block_comment(bytes, pos),
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+
+        result.push_str(&statement[copied..pos]);
+        result.push(' ');
+        copied = comment_end;
+        pos = comment_end;
+    }
+
+    result.push_str(&statement[copied..]);
+    result
+}
+
+fn starts_with_keyword(sql: &str, keyword: &str) -> bool {
+    sql.len() > keyword.len()
+        && sql.as_bytes()[..keyword.len()].eq_ignore_ascii_case(keyword.as_bytes())
+        && sql.as_bytes()[keyword.len()].is_ascii_whitespace()
+}
+
+/// The double dash starts a comment only when followed by a whitespace
+fn is_line_comment(bytes: &[u8], pos: usize) -> bool {
+    bytes.get(pos + 1) == Some(&b'-')
+        && bytes
+            .get(pos + 2)
+            .map(|c| c.is_ascii_whitespace())
+            .unwrap_or(true)
+}
+
+/// Returns the position right after the closing quote
+fn skip_quoted(bytes: &[u8], pos: usize, quote: u8) -> usize {
+    let mut pos = pos + 1;
+
+    while pos < bytes.len() {
+        if quote != b'`' && bytes[pos] == b'\\' {
+            pos += 2;
+        } else if bytes[pos] == quote {
+            if bytes.get(pos + 1) == Some(&quote) {
+                pos += 2;
+            } else {
+                return pos + 1;
+            }
+        } else {
+            pos += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+fn skip_line_comment(bytes: &[u8], pos: usize) -> usize {
+    match bytes[pos..].iter().position(|c| *c == b'\n') {
+        Some(offset) => pos + offset + 1,
+        None => bytes.len(),
+    }
+}
+
+/// Block comments are not nested in MySQL
+fn skip_block_comment(bytes: &[u8], pos: usize) -> usize {
+    let mut pos = pos + 2;
+
+    while pos < bytes.len() {
+        if bytes[pos] == b'*' && bytes.get(pos + 1) == Some(&b'/') {
+            return pos + 2;
+        }
+
+        pos += 1;
+    }
+
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_simple() {
+        assert_eq!(
+            split("create table a (id int);\n\ncreate table b (id int)\n"),
+            vec!["create table a (id int)", "create table b (id int)"]
+        );
+    }
+
+    #[test]
+    fn test_split_literals_and_comments() {
+        assert_eq!(
+            split("select 'a;\\'b', `c;d`; # e;\nselect 1--2;\n-- f;\n/* g; */ select 3;"),
+            vec!["select 'a;\\'b', `c;d`", "# e;\nselect 1--2", "-- f;\n/* g; */ select 3"]
+        );
+    }
+
+    #[test]
+    fn test_split_delimiter() {
+        let procedure = "create procedure p() begin select 1; select 2; end";
+
+        assert_eq!(
+            split(&format!("delimiter //\n{}//\ndelimiter ;\nselect 3;", procedure)),
+            vec![procedure, "select 3"]
+        );
+    }
+
+    #[test]
+    fn test_implicit_commit() {
+        assert!(causes_implicit_commit("create table a (id int)"));
+        assert!(causes_implicit_commit("/* x */ ALTER TABLE a add b int"));
+        assert!(!causes_implicit_commit("create temporary table a (id int)"));
+        assert!(!causes_implicit_commit("insert into a values ('create')"));
+        assert!(!causes_implicit_commit("-- drop table a\nupdate a set id = 1"));
+    }
+}
@@ -1,22 +1,33 @@
 -- ---
 -- version: 0.1
 -- ...
--- timestamp: !!timestamp 2019-06-23T20:21:38.495+00:00
+-- timestamp: !!timestamp 2026-10-18T07:46:47.492+00:00
 -- manifest:
 --   - sql/warden.sql
 --   - sql/warden/action_log.sql
 --   - sql/warden/api/do_log.sql
+--   - sql/warden/api/do_complete_migration.sql
 --   - sql/warden/api/do_deploy_migration.sql
+--   - sql/warden/api/do_fail_migration.sql
 --   - sql/warden/api/do_register_migration.sql
+--   - sql/warden/api/do_revert_migration.sql
+--   - sql/warden/api/get_action_log.sql
 --   - sql/warden/migration.sql
---   - sql/warden/api/get_latest_deployed_migration.sql
---   - sql/warden/do_base36_decode.sql
---   - sql/warden/do_base36_encode.sql
+--   - sql/warden/api/get_deployed_migrations.sql
 --   - sql/warden/migration/seal.sql
+--   - sql/warden/api/get_history.sql
+--   - sql/warden/api/get_latest_deployed_migration.sql
 --   - sql/warden/migration/snapshot.sql
+--   - sql/warden/api/get_migration_snapshot.sql
 --   - sql/warden/migration/source.sql
+--   - sql/warden/api/get_registered_migration.sql
+--   - sql/warden/api/get_registered_migrations.sql
+--   - sql/warden/do_base36_decode.sql
+--   - sql/warden/do_base36_encode.sql
+--   - sql/warden/migration/revert.sql
 --   - sql/warden/migration/triggers/fn__id_base36__populate.sql
 --   - sql/warden/migration/triggers/tp__id_base36__populate.sql
+--   - sql/warden/schema_version.sql
 -- ...
 
 -- BEGIN: sql/warden.sql
@@ -50,6 +61,51 @@ $$ language sql;
 
 -- END: sql/warden/api/do_log.sql
 
+-- BEGIN: sql/warden/api/do_complete_migration.sql
+
+-- ---
+-- require:
+--   - ./do_log.sql
+
+create function
+  warden.do_complete_migration(
+    id_ bigint
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+  deploy_ts_ timestamp with time zone;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name,
+    m.deploy_ts
+  into
+    migration_fullname_,
+    deploy_ts_
+  from
+    warden.migration m
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  if deploy_ts_ is not null then
+    raise exception 'Migration % has already been deployed', migration_fullname_;
+  end if;
+
+  update warden.migration set deploy_ts = now(), partial_ts = null where id = id_;
+
+  perform warden.do_log('Deployed | %s', migration_fullname_);
+end;
+$$ language plpgsql;
+
+comment on function warden.do_complete_migration is 'Marks a migration executed by the client as deployed';
+
+-- END: sql/warden/api/do_complete_migration.sql
+
 -- BEGIN: sql/warden/api/do_deploy_migration.sql
 
 -- ---
@@ -107,6 +163,45 @@ $$ language plpgsql;
 
 -- END: sql/warden/api/do_deploy_migration.sql
 
+-- BEGIN: sql/warden/api/do_fail_migration.sql
+
+-- ---
+-- require:
+--   - ./do_log.sql
+
+create function
+  warden.do_fail_migration(
+    id_ bigint,
+    error_ text
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name
+  into
+    migration_fullname_
+  from
+    warden.migration m
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  update warden.migration set partial_ts = now() where id = id_;
+
+  perform warden.do_log('Partially applied | %s | %s', migration_fullname_, error_);
+end;
+$$ language plpgsql;
+
+comment on function warden.do_fail_migration is 'Records a failed deployment of a migration executed outside of a transaction';
+
+-- END: sql/warden/api/do_fail_migration.sql
+
 -- BEGIN: sql/warden/api/do_register_migration.sql
 
 -- ---
@@ -122,12 +217,17 @@ create function
     snapshot_data_ bytea,
     seal_generated_at_ timestamp with time zone,
     seal_algo_ varchar(16),
-    seal_data_ bytea
+    seal_data_ bytea,
+    revert_source_ text,
+    seal_revert_data_ bytea
   )
 returns void
 as $$
 declare
   migration_fullname_ text = id_::text || '--' || name_;
+  deploy_ts_ timestamp with time zone;
+  partial_ts_ timestamp with time zone;
+  registered_ boolean;
 begin
   if id_ is null then
     raise exception 'Migration ID cannot be null';
@@ -149,10 +249,27 @@ begin
     raise exception 'Migration SnapshotData cannot be null';
   end if;
 
-  insert into warden.migration (id, name) values (id_, name_);
+  select true, m.deploy_ts, m.partial_ts into registered_, deploy_ts_, partial_ts_ from warden.migration m where m.id = id_;
+
+  if deploy_ts_ is not null then
+    raise exception 'Migration % has already been deployed', migration_fullname_;
+  end if;
+
+  if registered_ then
+    -- the migration has been reverted (or partially applied), so we register it anew
+    delete from warden.migration where id = id_;
+    perform warden.do_log('Unregistered | %s', migration_fullname_);
+  end if;
+
+  -- a partially applied migration stays so until it is completed
+  insert into warden.migration (id, name, partial_ts) values (id_, name_, partial_ts_);
   insert into warden.migration_source (migration_id, data) values (id_, source_);
   insert into warden.migration_snapshot (migration_id, format, data) values (id_, snapshot_format_, snapshot_data_);
-  insert into warden.migration_seal (migration_id, generated_at, algo, value) values (id_, seal_generated_at_, seal_algo_, seal_data_);
+  insert into warden.migration_seal (migration_id, generated_at, algo, value, revert_value) values (id_, seal_generated_at_, seal_algo_, seal_data_, seal_revert_data_);
+
+  if revert_source_ is not null then
+    insert into warden.migration_revert (migration_id, data) values (id_, revert_source_);
+  end if;
 
   perform warden.do_log('Registered | %s', migration_fullname_);
 
@@ -165,6 +282,114 @@ $$ language plpgsql;
 
 -- END: sql/warden/api/do_register_migration.sql
 
+-- BEGIN: sql/warden/api/do_revert_migration.sql
+
+-- ---
+-- require:
+--   - ./do_log.sql
+
+create function
+  warden.do_revert_migration(
+    id_ bigint
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+  deploy_ts_ timestamp with time zone;
+  revert_ text;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name,
+    m.deploy_ts,
+    r.data
+  into
+    migration_fullname_,
+    deploy_ts_,
+    revert_
+  from
+    warden.migration m
+  left join
+    warden.migration_revert r
+  on
+    r.migration_id = m.id
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  if deploy_ts_ is null then
+    raise exception 'Migration % has not been deployed', migration_fullname_;
+  end if;
+
+  if revert_ is null then
+    raise exception 'Migration % has no revert source', migration_fullname_;
+  end if;
+
+  execute revert_;
+
+  update warden.migration set deploy_ts = null where id = id_;
+
+  perform warden.do_log('Reverted | %s', migration_fullname_);
+
+exception
+  when others then
+    perform warden.do_log('Revert migration error: %s', SQLERRM);
+    raise;
+end;
+$$ language plpgsql;
+
+-- END: sql/warden/api/do_revert_migration.sql
+
+-- BEGIN: sql/warden/api/get_action_log.sql
+
+-- ---
+-- require:
+--   - /warden/action_log.sql
+
+create function
+  warden.get_action_log(
+    since_ timestamp with time zone,
+    user_ text,
+    grep_ text,
+    after_id_ bigint
+  )
+returns table (
+  id bigint,
+  ts timestamp with time zone,
+  "user" text,
+  client_address text,
+  session_pid int,
+  the_action text
+)
+as $$
+  select
+    l.id,
+    l.ts,
+    l."user"::text,
+    host(l.client_address),
+    l.session_pid,
+    l.the_action
+  from
+    warden.action_log l
+  where
+    (since_ is null or l.ts >= since_)
+  and
+    (user_ is null or l."user" = user_)
+  and
+    (grep_ is null or l.the_action ~ grep_)
+  and
+    (after_id_ is null or l.id > after_id_)
+  order by
+    l.id;
+$$ language sql;
+
+comment on function warden.get_action_log is 'Action log records, the grep is a POSIX regular expression';
+
+-- END: sql/warden/api/get_action_log.sql
+
 -- BEGIN: sql/warden/migration.sql
 
 create table warden.migration (
@@ -172,7 +397,8 @@ create table warden.migration (
   id_base36 varchar(32) not null unique,
   name text,
   sync_ts timestamp with time zone not null default current_timestamp,
-  deploy_ts timestamp with time zone null default null
+  deploy_ts timestamp with time zone null default null,
+  partial_ts timestamp with time zone null default null
 );
 
 comment on table warden.migration is 'Registered migrations';
@@ -181,9 +407,107 @@ comment on column warden.migration.id_base36 is 'Migration ID Base36 representat
 comment on column warden.migration.name is 'Migration name';
 comment on column warden.migration.sync_ts is 'Registration timestamp';
 comment on column warden.migration.deploy_ts is 'Migration deployment timestamp (null if it hasn''t been deployed)';
+comment on column warden.migration.partial_ts is 'Timestamp of a failed deployment outside of a transaction (the migration may be partially applied)';
 
 -- END: sql/warden/migration.sql
 
+-- BEGIN: sql/warden/api/get_deployed_migrations.sql
+
+-- ---
+-- require:
+--   - /warden/migration.sql
+
+create function
+  warden.get_deployed_migrations()
+  returns setof bigint
+as $$
+  select id from warden.migration where deploy_ts is not null order by id;
+$$ language sql;
+
+-- END: sql/warden/api/get_deployed_migrations.sql
+
+-- BEGIN: sql/warden/migration/seal.sql
+
+create table warden.migration_seal (
+  migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
+  generated_at timestamp with time zone not null,
+  algo varchar(16) not null,
+  value bytea,
+  revert_value bytea null
+);
+
+comment on table warden.migration_seal is 'Migration seal (signature)';
+comment on column warden.migration_seal.generated_at is 'The seal generation timestamp';
+comment on column warden.migration_seal.algo is 'Seal calculation algorithm';
+comment on column warden.migration_seal.value is 'Seal value. Signature generated according to the algorithm';
+comment on column warden.migration_seal.revert_value is 'Seal value of the revert source (null if the migration has no revert)';
+
+-- END: sql/warden/migration/seal.sql
+
+-- BEGIN: sql/warden/api/get_history.sql
+
+-- ---
+-- require:
+--   - /warden/migration.sql
+--   - /warden/migration/seal.sql
+
+create function
+  warden.get_history(
+    since_ timestamp with time zone,
+    pending_ boolean,
+    limit_ integer
+  )
+returns table (
+  uid text,
+  name text,
+  sync_ts timestamp with time zone,
+  deploy_ts timestamp with time zone,
+  partial_ts timestamp with time zone,
+  seal_algo varchar(16),
+  seal_value bytea
+)
+as $$
+  select
+    h.uid,
+    h.name,
+    h.sync_ts,
+    h.deploy_ts,
+    h.partial_ts,
+    h.seal_algo,
+    h.seal_value
+  from (
+    select
+      m.id,
+      lpad(m.id_base36, 6, '0') as uid,
+      m.name,
+      m.sync_ts,
+      m.deploy_ts,
+      m.partial_ts,
+      l.algo as seal_algo,
+      l.value as seal_value
+    from
+      warden.migration m
+    left join
+      warden.migration_seal l
+    on
+      l.migration_id = m.id
+    where
+      (since_ is null or m.sync_ts >= since_ or m.deploy_ts >= since_)
+    and
+      (not pending_ or m.deploy_ts is null)
+    order by
+      m.id desc
+    limit
+      limit_
+  ) h
+  order by
+    h.id;
+$$ language sql;
+
+comment on function warden.get_history is 'Registered migrations (the latest ones if limited), registered or deployed since the timestamp';
+
+-- END: sql/warden/api/get_history.sql
+
 -- BEGIN: sql/warden/api/get_latest_deployed_migration.sql
 
 -- ---
@@ -199,6 +523,119 @@ $$ language sql;
 
 -- END: sql/warden/api/get_latest_deployed_migration.sql
 
+-- BEGIN: sql/warden/migration/snapshot.sql
+
+create table warden.migration_snapshot (
+  migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
+  "format" varchar(32) not null,
+  data bytea
+);
+
+comment on table warden.migration_snapshot is 'Migration snapshots';
+comment on column warden.migration_snapshot.format is 'Datum format (e.g. .tar.gz)';
+comment on column warden.migration_snapshot.data is 'The snapshot data';
+
+-- END: sql/warden/migration/snapshot.sql
+
+-- BEGIN: sql/warden/api/get_migration_snapshot.sql
+
+-- ---
+-- require:
+--   - /warden/migration/snapshot.sql
+
+create function
+  warden.get_migration_snapshot(
+    id_ bigint
+  )
+returns table (
+  "format" varchar(32),
+  data bytea
+)
+as $$
+  select
+    s.format,
+    s.data
+  from
+    warden.migration_snapshot s
+  where
+    s.migration_id = id_;
+$$ language sql;
+
+-- END: sql/warden/api/get_migration_snapshot.sql
+
+-- BEGIN: sql/warden/migration/source.sql
+
+create table warden.migration_source (
+  migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
+  data text
+);
+
+comment on table warden.migration_source is 'Migration source (SQL)';
+comment on column warden.migration_source.data is 'The actual migration SQL to be executed';
+
+-- END: sql/warden/migration/source.sql
+
+-- BEGIN: sql/warden/api/get_registered_migration.sql
+
+-- ---
+-- require:
+--   - /warden/migration.sql
+--   - /warden/migration/seal.sql
+--   - /warden/migration/source.sql
+
+create function
+  warden.get_registered_migration(
+    id_ bigint
+  )
+returns table (
+  uid text,
+  name text,
+  source text,
+  seal_generated_at timestamp with time zone,
+  seal_algo varchar(16),
+  seal_value bytea,
+  seal_revert_value bytea
+)
+as $$
+  select
+    lpad(m.id_base36, 6, '0'),
+    m.name,
+    s.data,
+    l.generated_at,
+    l.algo,
+    l.value,
+    l.revert_value
+  from
+    warden.migration m
+  left join
+    warden.migration_source s
+  on
+    s.migration_id = m.id
+  left join
+    warden.migration_seal l
+  on
+    l.migration_id = m.id
+  where
+    m.id = id_;
+$$ language sql;
+
+-- END: sql/warden/api/get_registered_migration.sql
+
+-- BEGIN: sql/warden/api/get_registered_migrations.sql
+
+-- ---
+-- require:
+--   - /warden/migration.sql
+
+create function
+  warden.get_registered_migrations()
+  returns setof bigint
+as $$
+  select id from warden.migration order by id;
+$$ language sql;
+
+-- END: sql/warden/api/get_registered_migrations.sql
+
 -- BEGIN: sql/warden/do_base36_decode.sql
 
 create or replace function
@@ -268,47 +705,17 @@ comment on function warden.do_base36_encode is 'Encodes integer into base36 stri
 
 -- END: sql/warden/do_base36_encode.sql
 
--- BEGIN: sql/warden/migration/seal.sql
-
-create table warden.migration_seal (
-  migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
-  generated_at timestamp with time zone not null,
-  algo varchar(16) not null,
-  value bytea
-);
-
-comment on table warden.migration_seal is 'Migration seal (signature)';
-comment on column warden.migration_seal.generated_at is 'The seal generation timestamp';
-comment on column warden.migration_seal.algo is 'Seal calculation algorithm';
-comment on column warden.migration_seal.value is 'Seal value. Signature generated according to the algorithm';
+-- BEGIN: sql/warden/migration/revert.sql
 
--- END: sql/warden/migration/seal.sql
-
--- BEGIN: sql/warden/migration/snapshot.sql
-
-create table warden.migration_snapshot (
-  migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
-  "format" varchar(32) not null,
-  data bytea
-);
-
-comment on table warden.migration_snapshot is 'Migration snapshots';
-comment on column warden.migration_snapshot.format is 'Datum format (e.g. .tar.gz)';
-comment on column warden.migration_snapshot.data is 'The snapshot data';
-
--- END: sql/warden/migration/snapshot.sql
-
--- BEGIN: sql/warden/migration/source.sql
-
-create table warden.migration_source (
+create table warden.migration_revert (
   migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
   data text
 );
 
-comment on table warden.migration_source is 'Migration source (SQL)';
-comment on column warden.migration_source.data is 'The actual migration SQL to be executed';
+comment on table warden.migration_revert is 'Migration revert source (SQL)';
+comment on column warden.migration_revert.data is 'The SQL to be executed to revert the migration';
 
--- END: sql/warden/migration/source.sql
+-- END: sql/warden/migration/revert.sql
 
 -- BEGIN: sql/warden/migration/triggers/fn__id_base36__populate.sql
 
@@ -338,4 +745,19 @@ for
 execute procedure
   warden.tf__migration__id_base36__populate();
 
--- END: sql/warden/migration/triggers/tp__id_base36__populate.sql
\ No newline at end of file
+-- END: sql/warden/migration/triggers/tp__id_base36__populate.sql
+
+-- BEGIN: sql/warden/schema_version.sql
+
+create table warden.schema_version (
+  version int not null primary key,
+  upgrade_ts timestamp with time zone not null default current_timestamp
+);
+
+comment on table warden.schema_version is 'Versions of the warden schema (the highest one is the current)';
+comment on column warden.schema_version.version is 'The internal schema version';
+comment on column warden.schema_version.upgrade_ts is 'When the schema has been upgraded to the version';
+
+insert into warden.schema_version (version) values (1);
+
+-- END: sql/warden/schema_version.sql
\ No newline at end of file
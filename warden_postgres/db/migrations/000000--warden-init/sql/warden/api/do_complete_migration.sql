@@ -0,0 +1,40 @@
+-- ---
+-- require:
+--   - ./do_log.sql
+
+create function
+  warden.do_complete_migration(
+    id_ bigint
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+  deploy_ts_ timestamp with time zone;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name,
+    m.deploy_ts
+  into
+    migration_fullname_,
+    deploy_ts_
+  from
+    warden.migration m
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  if deploy_ts_ is not null then
+    raise exception 'Migration % has already been deployed', migration_fullname_;
+  end if;
+
+  update warden.migration set deploy_ts = now(), partial_ts = null where id = id_;
+
+  perform warden.do_log('Deployed | %s', migration_fullname_);
+end;
+$$ language plpgsql;
+
+comment on function warden.do_complete_migration is 'Marks a migration executed by the client as deployed';
@@ -0,0 +1,34 @@
+-- ---
+-- require:
+--   - ./do_log.sql
+
+create function
+  warden.do_fail_migration(
+    id_ bigint,
+    error_ text
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name
+  into
+    migration_fullname_
+  from
+    warden.migration m
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  update warden.migration set partial_ts = now() where id = id_;
+
+  perform warden.do_log('Partially applied | %s | %s', migration_fullname_, error_);
+end;
+$$ language plpgsql;
+
+comment on function warden.do_fail_migration is 'Records a failed deployment of a migration executed outside of a transaction';
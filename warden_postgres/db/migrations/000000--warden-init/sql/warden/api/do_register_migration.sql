@@ -11,12 +11,17 @@ create function
     snapshot_data_ bytea,
     seal_generated_at_ timestamp with time zone,
     seal_algo_ varchar(16),
-    seal_data_ bytea
+    seal_data_ bytea,
+    revert_source_ text,
+    seal_revert_data_ bytea
   )
 returns void
 as $$
 declare
   migration_fullname_ text = id_::text || '--' || name_;
+  deploy_ts_ timestamp with time zone;
+  partial_ts_ timestamp with time zone;
+  registered_ boolean;
 begin
   if id_ is null then
     raise exception 'Migration ID cannot be null';
@@ -38,10 +43,27 @@ begin
     raise exception 'Migration SnapshotData cannot be null';
   end if;
 
-  insert into warden.migration (id, name) values (id_, name_);
+  select true, m.deploy_ts, m.partial_ts into registered_, deploy_ts_, partial_ts_ from warden.migration m where m.id = id_;
+
+  if deploy_ts_ is not null then
+    raise exception 'Migration % has already been deployed', migration_fullname_;
+  end if;
+
+  if registered_ then
+    -- the migration has been reverted (or partially applied), so we register it anew
+    delete from warden.migration where id = id_;
+    perform warden.do_log('Unregistered | %s', migration_fullname_);
+  end if;
+
+  -- a partially applied migration stays so until it is completed
+  insert into warden.migration (id, name, partial_ts) values (id_, name_, partial_ts_);
   insert into warden.migration_source (migration_id, data) values (id_, source_);
   insert into warden.migration_snapshot (migration_id, format, data) values (id_, snapshot_format_, snapshot_data_);
-  insert into warden.migration_seal (migration_id, generated_at, algo, value) values (id_, seal_generated_at_, seal_algo_, seal_data_);
+  insert into warden.migration_seal (migration_id, generated_at, algo, value, revert_value) values (id_, seal_generated_at_, seal_algo_, seal_data_, seal_revert_data_);
+
+  if revert_source_ is not null then
+    insert into warden.migration_revert (migration_id, data) values (id_, revert_source_);
+  end if;
 
   perform warden.do_log('Registered | %s', migration_fullname_);
 
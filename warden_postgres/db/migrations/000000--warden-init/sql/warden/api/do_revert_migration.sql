@@ -0,0 +1,56 @@
+-- ---
+-- require:
+--   - ./do_log.sql
+
+create function
+  warden.do_revert_migration(
+    id_ bigint
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+  deploy_ts_ timestamp with time zone;
+  revert_ text;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name,
+    m.deploy_ts,
+    r.data
+  into
+    migration_fullname_,
+    deploy_ts_,
+    revert_
+  from
+    warden.migration m
+  left join
+    warden.migration_revert r
+  on
+    r.migration_id = m.id
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  if deploy_ts_ is null then
+    raise exception 'Migration % has not been deployed', migration_fullname_;
+  end if;
+
+  if revert_ is null then
+    raise exception 'Migration % has no revert source', migration_fullname_;
+  end if;
+
+  execute revert_;
+
+  update warden.migration set deploy_ts = null where id = id_;
+
+  perform warden.do_log('Reverted | %s', migration_fullname_);
+
+exception
+  when others then
+    perform warden.do_log('Revert migration error: %s', SQLERRM);
+    raise;
+end;
+$$ language plpgsql;
@@ -0,0 +1,42 @@
+-- ---
+-- require:
+--   - /warden/action_log.sql
+
+create function
+  warden.get_action_log(
+    since_ timestamp with time zone,
+    user_ text,
+    grep_ text,
+    after_id_ bigint
+  )
+returns table (
+  id bigint,
+  ts timestamp with time zone,
+  "user" text,
+  client_address text,
+  session_pid int,
+  the_action text
+)
+as $$
+  select
+    l.id,
+    l.ts,
+    l."user"::text,
+    host(l.client_address),
+    l.session_pid,
+    l.the_action
+  from
+    warden.action_log l
+  where
+    (since_ is null or l.ts >= since_)
+  and
+    (user_ is null or l."user" = user_)
+  and
+    (grep_ is null or l.the_action ~ grep_)
+  and
+    (after_id_ is null or l.id > after_id_)
+  order by
+    l.id;
+$$ language sql;
+
+comment on function warden.get_action_log is 'Action log records, the grep is a POSIX regular expression';
@@ -0,0 +1,10 @@
+-- ---
+-- require:
+--   - /warden/migration.sql
+
+create function
+  warden.get_deployed_migrations()
+  returns setof bigint
+as $$
+  select id from warden.migration where deploy_ts is not null order by id;
+$$ language sql;
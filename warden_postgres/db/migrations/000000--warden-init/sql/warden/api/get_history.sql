@@ -0,0 +1,59 @@
+-- ---
+-- require:
+--   - /warden/migration.sql
+--   - /warden/migration/seal.sql
+
+create function
+  warden.get_history(
+    since_ timestamp with time zone,
+    pending_ boolean,
+    limit_ integer
+  )
+returns table (
+  uid text,
+  name text,
+  sync_ts timestamp with time zone,
+  deploy_ts timestamp with time zone,
+  partial_ts timestamp with time zone,
+  seal_algo varchar(16),
+  seal_value bytea
+)
+as $$
+  select
+    h.uid,
+    h.name,
+    h.sync_ts,
+    h.deploy_ts,
+    h.partial_ts,
+    h.seal_algo,
+    h.seal_value
+  from (
+    select
+      m.id,
+      lpad(m.id_base36, 6, '0') as uid,
+      m.name,
+      m.sync_ts,
+      m.deploy_ts,
+      m.partial_ts,
+      l.algo as seal_algo,
+      l.value as seal_value
+    from
+      warden.migration m
+    left join
+      warden.migration_seal l
+    on
+      l.migration_id = m.id
+    where
+      (since_ is null or m.sync_ts >= since_ or m.deploy_ts >= since_)
+    and
+      (not pending_ or m.deploy_ts is null)
+    order by
+      m.id desc
+    limit
+      limit_
+  ) h
+  order by
+    h.id;
+$$ language sql;
+
+comment on function warden.get_history is 'Registered migrations (the latest ones if limited), registered or deployed since the timestamp';
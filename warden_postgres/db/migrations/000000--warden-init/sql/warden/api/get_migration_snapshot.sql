@@ -0,0 +1,21 @@
+-- ---
+-- require:
+--   - /warden/migration/snapshot.sql
+
+create function
+  warden.get_migration_snapshot(
+    id_ bigint
+  )
+returns table (
+  "format" varchar(32),
+  data bytea
+)
+as $$
+  select
+    s.format,
+    s.data
+  from
+    warden.migration_snapshot s
+  where
+    s.migration_id = id_;
+$$ language sql;
@@ -0,0 +1,41 @@
+-- ---
+-- require:
+--   - /warden/migration.sql
+--   - /warden/migration/seal.sql
+--   - /warden/migration/source.sql
+
+create function
+  warden.get_registered_migration(
+    id_ bigint
+  )
+returns table (
+  uid text,
+  name text,
+  source text,
+  seal_generated_at timestamp with time zone,
+  seal_algo varchar(16),
+  seal_value bytea,
+  seal_revert_value bytea
+)
+as $$
+  select
+    lpad(m.id_base36, 6, '0'),
+    m.name,
+    s.data,
+    l.generated_at,
+    l.algo,
+    l.value,
+    l.revert_value
+  from
+    warden.migration m
+  left join
+    warden.migration_source s
+  on
+    s.migration_id = m.id
+  left join
+    warden.migration_seal l
+  on
+    l.migration_id = m.id
+  where
+    m.id = id_;
+$$ language sql;
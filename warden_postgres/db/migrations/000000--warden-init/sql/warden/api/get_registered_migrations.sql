@@ -0,0 +1,10 @@
+-- ---
+-- require:
+--   - /warden/migration.sql
+
+create function
+  warden.get_registered_migrations()
+  returns setof bigint
+as $$
+  select id from warden.migration order by id;
+$$ language sql;
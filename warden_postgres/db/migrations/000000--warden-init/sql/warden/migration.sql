@@ -3,7 +3,8 @@ create table warden.migration (
   id_base36 varchar(32) not null unique,
   name text,
   sync_ts timestamp with time zone not null default current_timestamp,
-  deploy_ts timestamp with time zone null default null
+  deploy_ts timestamp with time zone null default null,
+  partial_ts timestamp with time zone null default null
 );
 
 comment on table warden.migration is 'Registered migrations';
@@ -12,3 +13,4 @@ comment on column warden.migration.id_base36 is 'Migration ID Base36 representat
 comment on column warden.migration.name is 'Migration name';
 comment on column warden.migration.sync_ts is 'Registration timestamp';
 comment on column warden.migration.deploy_ts is 'Migration deployment timestamp (null if it hasn''t been deployed)';
+comment on column warden.migration.partial_ts is 'Timestamp of a failed deployment outside of a transaction (the migration may be partially applied)';
@@ -0,0 +1,7 @@
+create table warden.migration_revert (
+  migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
+  data text
+);
+
+comment on table warden.migration_revert is 'Migration revert source (SQL)';
+comment on column warden.migration_revert.data is 'The SQL to be executed to revert the migration';
@@ -0,0 +1,10 @@
+create table warden.schema_version (
+  version int not null primary key,
+  upgrade_ts timestamp with time zone not null default current_timestamp
+);
+
+comment on table warden.schema_version is 'Versions of the warden schema (the highest one is the current)';
+comment on column warden.schema_version.version is 'The internal schema version';
+comment on column warden.schema_version.upgrade_ts is 'When the schema has been upgraded to the version';
+
+insert into warden.schema_version (version) values (1);
@@ -0,0 +1,384 @@
+-- Upgrades the warden schema created by the initial migration of the
+-- releases preceding the schema versioning up to version 1
+
+alter table warden.migration add column partial_ts timestamp with time zone null default null;
+comment on column warden.migration.partial_ts is 'Timestamp of a failed deployment outside of a transaction (the migration may be partially applied)';
+
+alter table warden.migration_seal add column revert_value bytea null;
+comment on column warden.migration_seal.revert_value is 'Seal value of the revert source (null if the migration has no revert)';
+
+create table warden.migration_revert (
+  migration_id bigint not null primary key references warden.migration (id) on delete cascade on update cascade,
+  data text
+);
+
+comment on table warden.migration_revert is 'Migration revert source (SQL)';
+comment on column warden.migration_revert.data is 'The SQL to be executed to revert the migration';
+
+create table warden.schema_version (
+  version int not null primary key,
+  upgrade_ts timestamp with time zone not null default current_timestamp
+);
+
+comment on table warden.schema_version is 'Versions of the warden schema (the highest one is the current)';
+comment on column warden.schema_version.version is 'The internal schema version';
+comment on column warden.schema_version.upgrade_ts is 'When the schema has been upgraded to the version';
+
+insert into warden.schema_version (version) values (1);
+
+drop function warden.do_register_migration(bigint, text, text, varchar, bytea, timestamp with time zone, varchar, bytea);
+
+create function
+  warden.do_register_migration(
+    id_ bigint,
+    name_ text,
+    source_ text,
+    snapshot_format_ varchar(32),
+    snapshot_data_ bytea,
+    seal_generated_at_ timestamp with time zone,
+    seal_algo_ varchar(16),
+    seal_data_ bytea,
+    revert_source_ text,
+    seal_revert_data_ bytea
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text = id_::text || '--' || name_;
+  deploy_ts_ timestamp with time zone;
+  partial_ts_ timestamp with time zone;
+  registered_ boolean;
+begin
+  if id_ is null then
+    raise exception 'Migration ID cannot be null';
+  end if;
+
+  if name_ is null then
+    raise exception 'Migration Name cannot be null';
+  end if;
+
+  if source_ is null then
+    raise exception 'Migration Source cannot be null';
+  end if;
+
+  if snapshot_format_ is null then
+    raise exception 'Migration SnapshotFormat cannot be null';
+  end if;
+
+  if snapshot_data_ is null then
+    raise exception 'Migration SnapshotData cannot be null';
+  end if;
+
+  select true, m.deploy_ts, m.partial_ts into registered_, deploy_ts_, partial_ts_ from warden.migration m where m.id = id_;
+
+  if deploy_ts_ is not null then
+    raise exception 'Migration % has already been deployed', migration_fullname_;
+  end if;
+
+  if registered_ then
+    -- the migration has been reverted (or partially applied), so we register it anew
+    delete from warden.migration where id = id_;
+    perform warden.do_log('Unregistered | %s', migration_fullname_);
+  end if;
+
+  -- a partially applied migration stays so until it is completed
+  insert into warden.migration (id, name, partial_ts) values (id_, name_, partial_ts_);
+  insert into warden.migration_source (migration_id, data) values (id_, source_);
+  insert into warden.migration_snapshot (migration_id, format, data) values (id_, snapshot_format_, snapshot_data_);
+  insert into warden.migration_seal (migration_id, generated_at, algo, value, revert_value) values (id_, seal_generated_at_, seal_algo_, seal_data_, seal_revert_data_);
+
+  if revert_source_ is not null then
+    insert into warden.migration_revert (migration_id, data) values (id_, revert_source_);
+  end if;
+
+  perform warden.do_log('Registered | %s', migration_fullname_);
+
+exception
+  when others then
+    perform warden.do_log('Registering migration error: %s', SQLERRM);
+    raise;
+end;
+$$ language plpgsql;
+
+create function
+  warden.do_complete_migration(
+    id_ bigint
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+  deploy_ts_ timestamp with time zone;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name,
+    m.deploy_ts
+  into
+    migration_fullname_,
+    deploy_ts_
+  from
+    warden.migration m
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  if deploy_ts_ is not null then
+    raise exception 'Migration % has already been deployed', migration_fullname_;
+  end if;
+
+  update warden.migration set deploy_ts = now(), partial_ts = null where id = id_;
+
+  perform warden.do_log('Deployed | %s', migration_fullname_);
+end;
+$$ language plpgsql;
+
+comment on function warden.do_complete_migration is 'Marks a migration executed by the client as deployed';
+
+create function
+  warden.do_fail_migration(
+    id_ bigint,
+    error_ text
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name
+  into
+    migration_fullname_
+  from
+    warden.migration m
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  update warden.migration set partial_ts = now() where id = id_;
+
+  perform warden.do_log('Partially applied | %s | %s', migration_fullname_, error_);
+end;
+$$ language plpgsql;
+
+comment on function warden.do_fail_migration is 'Records a failed deployment of a migration executed outside of a transaction';
+
+create function
+  warden.do_revert_migration(
+    id_ bigint
+  )
+returns void
+as $$
+declare
+  migration_fullname_ text;
+  deploy_ts_ timestamp with time zone;
+  revert_ text;
+begin
+  select
+    lpad(m.id_base36, 6, '0') || '--' || m.name,
+    m.deploy_ts,
+    r.data
+  into
+    migration_fullname_,
+    deploy_ts_,
+    revert_
+  from
+    warden.migration m
+  left join
+    warden.migration_revert r
+  on
+    r.migration_id = m.id
+  where
+    m.id = id_;
+
+  if migration_fullname_ is null then
+    raise exception 'Unknown migration with "%"', warden.do_base36_encode(id_);
+  end if;
+
+  if deploy_ts_ is null then
+    raise exception 'Migration % has not been deployed', migration_fullname_;
+  end if;
+
+  if revert_ is null then
+    raise exception 'Migration % has no revert source', migration_fullname_;
+  end if;
+
+  execute revert_;
+
+  update warden.migration set deploy_ts = null where id = id_;
+
+  perform warden.do_log('Reverted | %s', migration_fullname_);
+
+exception
+  when others then
+    perform warden.do_log('Revert migration error: %s', SQLERRM);
+    raise;
+end;
+$$ language plpgsql;
+
+create function
+  warden.get_action_log(
+    since_ timestamp with time zone,
+    user_ text,
+    grep_ text,
+    after_id_ bigint
+  )
+returns table (
+  id bigint,
+  ts timestamp with time zone,
+  "user" text,
+  client_address text,
+  session_pid int,
+  the_action text
+)
+as $$
+  select
+    l.id,
+    l.ts,
+    l."user"::text,
+    host(l.client_address),
+    l.session_pid,
+    l.the_action
+  from
+    warden.action_log l
+  where
+    (since_ is null or l.ts >= since_)
+  and
+    (user_ is null or l."user" = user_)
+  and
+    (grep_ is null or l.the_action ~ grep_)
+  and
+    (after_id_ is null or l.id > after_id_)
+  order by
+    l.id;
+$$ language sql;
+
+comment on function warden.get_action_log is 'Action log records, the grep is a POSIX regular expression';
+
+create function
+  warden.get_deployed_migrations()
+  returns setof bigint
+as $$
+  select id from warden.migration where deploy_ts is not null order by id;
+$$ language sql;
+
+create function
+  warden.get_history(
+    since_ timestamp with time zone,
+    pending_ boolean,
+    limit_ integer
+  )
+returns table (
+  uid text,
+  name text,
+  sync_ts timestamp with time zone,
+  deploy_ts timestamp with time zone,
+  partial_ts timestamp with time zone,
+  seal_algo varchar(16),
+  seal_value bytea
+)
+as $$
+  select
+    h.uid,
+    h.name,
+    h.sync_ts,
+    h.deploy_ts,
+    h.partial_ts,
+    h.seal_algo,
+    h.seal_value
+  from (
+    select
+      m.id,
+      lpad(m.id_base36, 6, '0') as uid,
+      m.name,
+      m.sync_ts,
+      m.deploy_ts,
+      m.partial_ts,
+      l.algo as seal_algo,
+      l.value as seal_value
+    from
+      warden.migration m
+    left join
+      warden.migration_seal l
+    on
+      l.migration_id = m.id
+    where
+      (since_ is null or m.sync_ts >= since_ or m.deploy_ts >= since_)
+    and
+      (not pending_ or m.deploy_ts is null)
+    order by
+      m.id desc
+    limit
+      limit_
+  ) h
+  order by
+    h.id;
+$$ language sql;
+
+comment on function warden.get_history is 'Registered migrations (the latest ones if limited), registered or deployed since the timestamp';
+
+create function
+  warden.get_migration_snapshot(
+    id_ bigint
+  )
+returns table (
+  "format" varchar(32),
+  data bytea
+)
+as $$
+  select
+    s.format,
+    s.data
+  from
+    warden.migration_snapshot s
+  where
+    s.migration_id = id_;
+$$ language sql;
+
+create function
+  warden.get_registered_migration(
+    id_ bigint
+  )
+returns table (
+  uid text,
+  name text,
+  source text,
+  seal_generated_at timestamp with time zone,
+  seal_algo varchar(16),
+  seal_value bytea,
+  seal_revert_value bytea
+)
+as $$
+  select
+    lpad(m.id_base36, 6, '0'),
+    m.name,
+    s.data,
+    l.generated_at,
+    l.algo,
+    l.value,
+    l.revert_value
+  from
+    warden.migration m
+  left join
+    warden.migration_source s
+  on
+    s.migration_id = m.id
+  left join
+    warden.migration_seal l
+  on
+    l.migration_id = m.id
+  where
+    m.id = id_;
+$$ language sql;
+
+create function
+  warden.get_registered_migrations()
+  returns setof bigint
+as $$
+  select id from warden.migration order by id;
+$$ language sql;
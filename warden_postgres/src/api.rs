@@ -1,9 +1,13 @@
 use chrono::prelude::{DateTime, FixedOffset};
 use failure::Error;
 use postgres::transaction::Transaction;
+use postgres::GenericConnection;
+
+use crate::registry::render;
 
 pub fn do_register_migration(
     connection: &Transaction,
+    schema: &str,
     id: u128,
     name: &str,
     source: &str,
@@ -12,10 +16,12 @@ pub fn do_register_migration(
     seal_generated_at: &DateTime<FixedOffset>,
     seal_algo: &str,
     seal_data: &[u8],
+    revert_source: Option<&str>,
+    seal_revert_data: Option<&[u8]>,
 ) -> Result<(), Error> {
     Ok(connection
         .execute(
-            "select warden.do_register_migration(($1::text)::int8, $2, $3, $4, $5, $6, $7, $8)",
+            &render("select warden.do_register_migration(($1::text)::int8, $2, $3, $4, $5, $6, $7, $8, $9, $10)", schema),
             &[
                 // "postgres=0.15.2" does not support u128 yet, so we pass it as a string
                 &id.to_string(),
@@ -26,16 +32,75 @@ pub fn do_register_migration(
                 seal_generated_at,
                 &seal_algo,
                 &seal_data,
+                &revert_source,
+                &seal_revert_data,
             ],
         )
         .map(|_| ())?)
 }
 
-pub fn do_deploy_migration(connection: &Transaction, id: u128) -> Result<(), Error> {
+pub fn do_deploy_migration(connection: &Transaction, schema: &str, id: u128) -> Result<(), Error> {
+    Ok(connection
+        .execute(
+            &render(
+                "select warden.do_deploy_migration(($1::text)::int8)",
+                schema,
+            ),
+            &[&id.to_string()],
+        )
+        .map(|_| ())?)
+}
+
+pub fn do_revert_migration(connection: &Transaction, schema: &str, id: u128) -> Result<(), Error> {
     Ok(connection
         .execute(
-            "select warden.do_deploy_migration(($1::text)::int8)",
+            &render(
+                "select warden.do_revert_migration(($1::text)::int8)",
+                schema,
+            ),
             &[&id.to_string()],
         )
         .map(|_| ())?)
 }
+
+pub fn do_log(connection: &GenericConnection, schema: &str, action: &str) -> Result<(), Error> {
+    Ok(connection
+        .execute(
+            &render("select warden.do_log('%s', $1)", schema),
+            &[&action],
+        )
+        .map(|_| ())?)
+}
+
+pub fn do_complete_migration(
+    connection: &GenericConnection,
+    schema: &str,
+    id: u128,
+) -> Result<(), Error> {
+    Ok(connection
+        .execute(
+            &render(
+                "select warden.do_complete_migration(($1::text)::int8)",
+                schema,
+            ),
+            &[&id.to_string()],
+        )
+        .map(|_| ())?)
+}
+
+pub fn do_fail_migration(
+    connection: &GenericConnection,
+    schema: &str,
+    id: u128,
+    error: &str,
+) -> Result<(), Error> {
+    Ok(connection
+        .execute(
+            &render(
+                "select warden.do_fail_migration(($1::text)::int8, $2)",
+                schema,
+            ),
+            &[&id.to_string(), &error],
+        )
+        .map(|_| ())?)
+}
@@ -1,17 +1,43 @@
-use failure::Error;
+use failure::{Error, ResultExt};
 
 use crate::api;
-use path_abs::FileRead;
-use postgres::{self, transaction::Transaction, TlsMode};
+use crate::lock;
+use crate::registry;
+use crate::passfile;
+use crate::statement;
+use crate::tls;
+use crate::upgrade;
+use path_abs::{FileRead, PathArc};
+use postgres::params::{ConnectParams, Host, IntoConnectParams};
+use postgres::{self, transaction::Transaction, GenericConnection};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::env;
 use std::ops::Deref;
-use warden_core::migration::snapshot;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use warden_core::dbms::{ActionLogEntry, ActionLogFilter, HistoryEntry, HistoryFilter, Registration, Timeouts, Tls};
+use warden_core::dbms::ConnectionParams;
+use warden_core::migration::execution::ExecutionMode;
+use warden_core::migration::transaction::TransactionMode;
+use warden_core::migration::snapshot::{self, Snapshot};
+use warden_core::migration::{algo::Algo, identity::Identity, seal::Seal};
 use warden_core::{dbms, migration::meta::Meta};
 
+const DEFAULT_PORT: u16 = 5432;
+
 #[derive(Debug)]
 pub struct Connection {
     connection: postgres::Connection,
     catalog: String,
     initialised: bool,
+
+    /// The warden schema version (None if not initialised when connected)
+    schema_version: Option<i32>,
+
+    /// The schema keeping the warden data
+    schema: String,
 }
 
 impl dbms::Connection for Connection {
@@ -20,13 +46,13 @@ impl dbms::Connection for Connection {
     }
 
     fn get_last_deployed_migration(&self) -> Result<Option<u128>, Error> {
-        if !self.is_initialised()? {
+        if !self.is_ready()? {
             return Ok(None);
         }
 
         let result: i64 = self
             .connection
-            .query("select warden.get_latest_deployed_migration()", &[])?
+            .query(&self.render("select warden.get_latest_deployed_migration()"), &[])?
             .get(0)
             .get_opt(0)
             .ok_or(failure::err_msg("Could not fetch warden metadata"))??;
@@ -34,7 +60,130 @@ impl dbms::Connection for Connection {
         Ok(Some(result as u128))
     }
 
-    fn deploy(&self, meta: Meta) -> Result<(), Error> {
+    fn get_registered_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        if !self.is_ready()? {
+            return Ok(BTreeSet::new());
+        }
+
+        self.query_ids(&self.render("select warden.get_registered_migrations()"))
+    }
+
+    fn get_deployed_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        if !self.is_ready()? {
+            return Ok(BTreeSet::new());
+        }
+
+        self.query_ids(&self.render("select warden.get_deployed_migrations()"))
+    }
+
+    fn get_registered_migration(&self, id: u128) -> Result<Option<Registration>, Error> {
+        if !self.is_ready()? {
+            return Ok(None);
+        }
+
+        let rows = self.connection.query(
+            &self.render("select * from warden.get_registered_migration(($1::text)::int8)"),
+            &[&id.to_string()],
+        )?;
+
+        let row = if let Some(row) = rows.iter().next() {
+            row
+        } else {
+            return Ok(None);
+        };
+
+        let missing = || failure::err_msg("Could not fetch warden metadata");
+
+        let algo: String = row.get_opt(4).ok_or_else(missing)??;
+
+        Ok(Some(Registration {
+            identity: Identity::build(
+                row.get_opt(0).ok_or_else(missing)??,
+                row.get_opt(1).ok_or_else(missing)??,
+            ),
+            source: row.get_opt(2).ok_or_else(missing)??,
+            seal: Seal {
+                timestamp: row.get_opt(3).ok_or_else(missing)??,
+                algo: Algo::from_str(&algo)
+                    .ok_or(failure::err_msg(format!("unknown algo {}", algo)))?,
+                sign: row.get_opt(5).ok_or_else(missing)??,
+                revert_sign: row.get_opt(6).ok_or_else(missing)??,
+            },
+        }))
+    }
+
+    fn get_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, Error> {
+        if !self.is_ready()? {
+            return Ok(Vec::new());
+        }
+
+        let rows = self.connection.query(
+            &self.render("select * from warden.get_history($1, $2, $3)"),
+            &[
+                &filter.since,
+                &filter.pending,
+                &filter.limit.map(|limit| limit as i32),
+            ],
+        )?;
+
+        let missing = || failure::err_msg("Could not fetch warden metadata");
+
+        let mut result = Vec::with_capacity(rows.len());
+
+        for row in &rows {
+            result.push(HistoryEntry {
+                identity: Identity::build(
+                    row.get_opt(0).ok_or_else(missing)??,
+                    row.get_opt(1).ok_or_else(missing)??,
+                ),
+                registered_at: row.get_opt(2).ok_or_else(missing)??,
+                deployed_at: row.get_opt(3).ok_or_else(missing)??,
+                partially_applied_at: row.get_opt(4).ok_or_else(missing)??,
+                seal_algo: row.get_opt(5).ok_or_else(missing)??,
+                seal_sign: row.get_opt(6).ok_or_else(missing)??,
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn get_migration_snapshot(&self, id: u128) -> Result<Option<Snapshot>, Error> {
+        if !self.is_ready()? {
+            return Ok(None);
+        }
+
+        let rows = self.connection.query(
+            &self.render("select * from warden.get_migration_snapshot(($1::text)::int8)"),
+            &[&id.to_string()],
+        )?;
+
+        let row = if let Some(row) = rows.iter().next() {
+            row
+        } else {
+            return Ok(None);
+        };
+
+        let missing = || failure::err_msg("Could not fetch warden metadata");
+
+        let format: String = row.get_opt(0).ok_or_else(missing)??;
+
+        Ok(Some(Snapshot {
+            format: snapshot::Format::from(&format)?,
+            data: row.get_opt(1).ok_or_else(missing)??,
+        }))
+    }
+
+    fn lock(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        lock::acquire(&self.connection, &self.schema, timeout)
+    }
+
+    fn unlock(&self) -> Result<(), Error> {
+        lock::release(&self.connection, &self.schema)
+    }
+
+    fn deploy(&self, meta: Meta, timeouts: &Timeouts) -> Result<(), Error> {
+        self.ensure_schema_version()?;
+
         if !self.is_initialised()? {
             let id = meta.get_identity().get_id().unwrap_or(1);
 
@@ -50,33 +199,261 @@ impl dbms::Connection for Connection {
             }
         }
 
+        if meta.get_transaction() == TransactionMode::None {
+            return self.deploy_non_transactional(meta, timeouts);
+        }
+
+        let subject = format!(r#"Migration "{}""#, meta.get_identity());
+
+        self.retry_on_lock_timeout(&subject, timeouts, || {
+            let transaction = self.connection.transaction()?;
+            set_timeouts(&transaction, timeouts, true)?;
+            self.register_migration(&transaction, &meta)?;
+            self.deploy_migration(&transaction, &meta)?;
+            Ok(transaction.commit()?)
+        })
+    }
+
+    fn deploy_atomic(&self, migrations: Vec<Meta>, timeouts: &Timeouts) -> Result<(), (usize, Error)> {
+        if let Some(position) = migrations
+            .iter()
+            .position(|meta| meta.get_transaction() == TransactionMode::None)
+        {
+            return Err((
+                position,
+                failure::err_msg(format!(
+                    r#"Migration "{}" is not transactional and cannot be deployed atomically"#,
+                    migrations[position].get_identity()
+                )),
+            ));
+        }
+
+        self.ensure_schema_version().map_err(|error| (0, error))?;
+
+        let initialised = self.is_initialised().map_err(|error| (0, error))?;
+
+        if let Some(meta) = migrations.first() {
+            if !initialised && meta.get_identity().get_id() != Some(0) {
+                return Err((
+                    0,
+                    failure::err_msg(format!(
+                        r#"The database is not initialised with Warden. Error trying to deploy migration "{}". The initial migration must be deployed first"#,
+                        meta.get_identity()
+                    )),
+                ));
+            }
+        }
+
+        let mut failed = migrations.len();
+
+        let result = self.retry_on_lock_timeout("The atomic deploy", timeouts, || {
+            let transaction = self.connection.transaction()?;
+
+            for (position, meta) in migrations.iter().enumerate() {
+                failed = position;
+
+                if meta.get_identity().get_id() == Some(0) {
+                    self.deploy_initial_within(&transaction, meta)?;
+                    continue;
+                }
+
+                // the timeouts of the previous migration must not leak into this one
+                transaction.batch_execute("reset lock_timeout; reset statement_timeout")?;
+                set_timeouts(&transaction, &timeouts.for_migration(meta), true)?;
+
+                self.register_migration(&transaction, meta)?;
+                self.deploy_migration(&transaction, meta)?;
+            }
+
+            failed = migrations.len();
+            Ok(transaction.commit()?)
+        });
+
+        result.map_err(|error| (failed, error))
+    }
+
+    fn upgrade(&self) -> Result<Vec<String>, Error> {
+        if !self.is_initialised()? {
+            return Err(failure::err_msg(
+                "The database is not initialised with Warden, deploy the initial migration instead",
+            ));
+        }
+
         let transaction = self.connection.transaction()?;
-        self.register_migration(&transaction, &meta)?;
-        self.deploy_migration(&transaction, &meta)?;
+        let applied = upgrade::apply(&transaction, &self.schema)?;
+        transaction.commit()?;
+
+        Ok(applied.into_iter().map(str::to_string).collect())
+    }
+
+    fn log_action(&self, action: &str) -> Result<(), Error> {
+        if !self.is_initialised()? {
+            log::warn!("The database is not initialised with Warden, could not log: {}", action);
+            return Ok(());
+        }
+
+        api::do_log(&self.connection, &self.schema, action)
+    }
+
+    fn get_action_log(&self, filter: &ActionLogFilter) -> Result<Vec<ActionLogEntry>, Error> {
+        if !self.is_ready()? {
+            return Ok(Vec::new());
+        }
+
+        let rows = self.connection.query(
+            &self.render("select * from warden.get_action_log($1, $2, $3, $4)"),
+            &[&filter.since, &filter.user, &filter.grep, &filter.after_id],
+        )?;
+
+        let missing = || failure::err_msg("Could not fetch warden action log");
+
+        let mut result = Vec::with_capacity(rows.len());
+
+        for row in &rows {
+            result.push(ActionLogEntry {
+                id: row.get_opt(0).ok_or_else(missing)??,
+                ts: row.get_opt(1).ok_or_else(missing)??,
+                user: row.get_opt(2).ok_or_else(missing)??,
+                client_address: row.get_opt(3).ok_or_else(missing)??,
+                session_pid: row.get_opt(4).ok_or_else(missing)??,
+                action: row
+                    .get_opt::<_, Option<String>>(5)
+                    .ok_or_else(missing)??
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn revert(&self, meta: Meta) -> Result<(), Error> {
+        let id = meta
+            .get_identity()
+            .get_id()
+            .ok_or(failure::err_msg("could not decode migration id"))?;
+
+        if id == 0 {
+            return Err(failure::err_msg("The initial migration cannot be reverted"));
+        }
+
+        if !self.is_initialised()? {
+            return Err(failure::err_msg(format!(
+                r#"The database is not initialised with Warden. Error trying to revert migration "{}""#,
+                meta.get_identity()
+            )));
+        }
+
+        self.ensure_schema_version()?;
+
+        let transaction = self.connection.transaction()?;
+        api::do_revert_migration(&transaction, &self.schema, id)?;
         Ok(transaction.commit()?)
     }
 }
 
 impl Connection {
     fn is_initialised(&self) -> Result<bool, Error> {
-        Ok(self.initialised || is_initialised(&self.connection, &self.catalog)?)
+        Ok(self.initialised || is_initialised(&self.connection, &self.catalog, &self.schema)?)
+    }
+
+    /// The warden SQL rendered against the registry schema
+    fn render<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        registry::render(sql, &self.schema)
+    }
+
+    /// Whether the warden schema can be queried, i.e. it is initialised
+    /// and of the supported version
+    fn is_ready(&self) -> Result<bool, Error> {
+        if !self.is_initialised()? {
+            return Ok(false);
+        }
+
+        self.ensure_schema_version()?;
+
+        Ok(true)
+    }
+
+    /// Refuse to use the warden schema older than the binary is built for.
+    /// Everything but the upgrade (and the initial deploy) must check it
+    fn ensure_schema_version(&self) -> Result<(), Error> {
+        let current = if let Some(current) = self.schema_version {
+            current
+        } else {
+            // the initial migration is followed by all the upgrades
+            return Ok(());
+        };
+
+        let latest = upgrade::latest_version();
+
+        if current < latest {
+            Err(failure::err_msg(format!(
+                "The warden schema is at version {} while version {} is required, run `warden app upgrade` first",
+                current, latest
+            )))?
+        }
+
+        Ok(())
+    }
+
+    fn query_ids(&self, query: &str) -> Result<BTreeSet<u128>, Error> {
+        let mut result = BTreeSet::new();
+
+        for row in &self.connection.query(query, &[])? {
+            let id: i64 = row
+                .get_opt(0)
+                .ok_or(failure::err_msg("Could not fetch warden metadata"))??;
+
+            result.insert(id as u128);
+        }
+
+        Ok(result)
     }
 
     fn deploy_migration(&self, transaction: &Transaction, meta: &Meta) -> Result<(), Error> {
-        api::do_deploy_migration(
-            transaction,
-            meta.get_identity()
-                .get_id()
-                .ok_or(failure::err_msg("could not decode migration id"))?,
-        )
+        let id = meta
+            .get_identity()
+            .get_id()
+            .ok_or(failure::err_msg("could not decode migration id"))?;
+
+        match meta.get_execution() {
+            ExecutionMode::Server => api::do_deploy_migration(transaction, &self.schema, id),
+            ExecutionMode::Client => {
+                self.execute_on_client(transaction, meta)?;
+                api::do_complete_migration(transaction, &self.schema, id)
+            }
+        }
+    }
+
+    /// Send the statements of the migration one by one
+    fn execute_on_client(&self, transaction: &Transaction, meta: &Meta) -> Result<(), Error> {
+        let source = FileRead::read(meta.get_target())?.read_string()?;
+
+        for (num, sql) in statement::split(&source).into_iter().enumerate() {
+            log::debug!("Executing statement {}:\n{}", num + 1, sql);
+
+            transaction.batch_execute(sql).context(format!(
+                r#"Migration "{}", {}"#,
+                meta.get_identity(),
+                statement::describe(&source, sql, num + 1)
+            ))?;
+        }
+
+        Ok(())
     }
 
     fn register_migration(&self, transaction: &Transaction, meta: &Meta) -> Result<(), Error> {
         let snapshot = snapshot::Snapshot::take(snapshot::Format::TarGz, &meta)?;
         let seal = meta.get_seal_meta().read_the_seal()?;
 
+        let revert_source = if let Some(revert) = meta.get_revert() {
+            Some(FileRead::read(revert.get_target())?.read_string()?)
+        } else {
+            None
+        };
+
         api::do_register_migration(
             transaction,
+            &self.schema,
             meta.get_identity()
                 .get_id()
                 .ok_or(failure::err_msg("could not decode migration id"))?,
@@ -87,22 +464,117 @@ impl Connection {
             &seal.timestamp,
             seal.algo.stringify(),
             &seal.sign,
+            revert_source.as_ref().map(|s| s.as_str()),
+            seal.revert_sign.as_ref().map(|s| s.as_slice()),
         )
     }
 
+    /// Register the migration in its own transaction and then execute
+    /// its statements one by one, each of them committed on its own
+    fn deploy_non_transactional(&self, meta: Meta, timeouts: &Timeouts) -> Result<(), Error> {
+        let id = meta
+            .get_identity()
+            .get_id()
+            .ok_or(failure::err_msg("could not decode migration id"))?;
+
+        let transaction = self.connection.transaction()?;
+        self.register_migration(&transaction, &meta)?;
+        transaction.commit()?;
+
+        let source = FileRead::read(meta.get_target())?.read_string()?;
+
+        // there is no transaction to scope the timeouts, so they are
+        // set for the session until the migration is over
+        set_timeouts(&self.connection, timeouts, false)?;
+
+        for (num, sql) in statement::split(&source).into_iter().enumerate() {
+            log::debug!("Executing statement {}:\n{}", num + 1, sql);
+
+            let subject = format!(r#"Migration "{}", statement {}"#, meta.get_identity(), num + 1);
+
+            let result = self.retry_on_lock_timeout(&subject, timeouts, || {
+                Ok(self.connection.batch_execute(sql)?)
+            });
+
+            if let Err(error) = result {
+                let error = format!("{}: {}", statement::describe(&source, sql, num + 1), error);
+
+                // the bookkeeping failures are only logged, so that they never hide the error
+                if let Err(reset_error) = reset_timeouts(&self.connection, timeouts) {
+                    log::error!("Could not reset the timeouts: {}", reset_error);
+                }
+
+                if let Err(fail_error) = api::do_fail_migration(&self.connection, &self.schema, id, &error) {
+                    log::error!(
+                        r#"Could not mark migration "{}" as partially applied: {}"#,
+                        meta.get_identity(),
+                        fail_error
+                    );
+                }
+
+                return Err(failure::err_msg(format!(
+                    r#"Migration "{}" has been partially applied, {}"#,
+                    meta.get_identity(),
+                    error
+                )));
+            }
+        }
+
+        reset_timeouts(&self.connection, timeouts)?;
+        api::do_complete_migration(&self.connection, &self.schema, id)
+    }
+
+    /// Run the deployment again (with exponential backoff) while it keeps
+    /// failing because of the lock timeout, up to the configured number of retries
+    fn retry_on_lock_timeout<F>(&self, subject: &str, timeouts: &Timeouts, mut deploy: F) -> Result<(), Error>
+    where
+        F: FnMut() -> Result<(), Error>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            match deploy() {
+                Err(ref error) if attempt < timeouts.lock_retries && is_lock_timeout(error) => {
+                    let delay = Duration::from_secs(1 << attempt.min(6));
+
+                    log::warn!(
+                        "{} could not acquire a lock in time, retrying in {}s ({} of {})",
+                        subject,
+                        delay.as_secs(),
+                        attempt + 1,
+                        timeouts.lock_retries
+                    );
+
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     fn deploy_initial(&self, meta: Meta) -> Result<(), Error> {
         log::trace!("Deploying initial migration");
-        let sql = &FileRead::read(meta.get_target())?.read_string()?;
         let transaction = self.connection.transaction()?;
-        transaction.batch_execute(sql)?;
+        self.deploy_initial_within(&transaction, &meta)?;
 
-        self.register_migration(&transaction, &meta)?;
+        Ok(transaction.commit()?)
+    }
+
+    fn deploy_initial_within(&self, transaction: &Transaction, meta: &Meta) -> Result<(), Error> {
+        let sql = &FileRead::read(meta.get_target())?.read_string()?;
+        transaction.batch_execute(&self.render(sql))?;
+
+        // The projects created by the older versions keep the older init
+        upgrade::apply(transaction, &self.schema)?;
+
+        self.register_migration(transaction, meta)?;
         transaction.execute(
-            "update warden.migration set deploy_ts = now() where id = 0",
+            &self.render("update warden.migration set deploy_ts = now() where id = 0"),
             &[],
         )?;
 
-        Ok(transaction.commit()?)
+        Ok(())
     }
 }
 
@@ -114,8 +586,116 @@ impl Deref for Connection {
     }
 }
 
-pub fn open(url: &str) -> Result<Connection, Error> {
-    let connection = postgres::Connection::connect(url.clone(), TlsMode::None)?;
+/// Open the connection by the URL, the TLS settings of the URL taking precedence over the given ones
+pub fn open(url: &str, schema: &str, tls: &Tls) -> Result<Connection, Error> {
+    let (url, settings) = tls::Settings::parse(url, tls)?;
+
+    let params = url
+        .into_connect_params()
+        .map_err(|error| failure::err_msg(format!("Invalid connection URL: {}", error)))?;
+
+    connect(with_passfile(params, None)?, &settings, schema)
+}
+
+/// Open the connection with the params given field by field,
+/// defaulting to the local server and the database named after the user
+pub fn open_params(params: &ConnectionParams, schema: &str, tls: &Tls) -> Result<Connection, Error> {
+    let settings = tls::Settings::from_config(tls)?;
+
+    let host = match params.host {
+        Some(ref host) if host.starts_with('/') => Host::Unix(PathBuf::from(host)),
+        Some(ref host) => Host::Tcp(host.clone()),
+        None => Host::Tcp("localhost".to_string()),
+    };
+
+    let user = params
+        .user
+        .clone()
+        .or_else(|| env::var("USER").ok())
+        .ok_or_else(|| failure::err_msg("The database user is undefined"))?;
+
+    let password = if let Some(ref file) = params.password_file {
+        Some(read_password_file(file)?)
+    } else {
+        None
+    };
+
+    let connect_params = ConnectParams::builder()
+        .port(params.port.unwrap_or(DEFAULT_PORT))
+        .user(&user, password.as_deref())
+        .database(params.dbname.as_deref().unwrap_or(&user))
+        .build(host);
+
+    let passfile = params.passfile.as_ref().map(|path| path.as_path());
+
+    connect(with_passfile(connect_params, passfile)?, &settings, schema)
+}
+
+/// The first line of the file
+fn read_password_file(file: &PathArc) -> Result<String, Error> {
+    let content = FileRead::read(file)
+        .and_then(|mut file| file.read_string())
+        .with_context(|_| format!("Could not read the password file {:?}", file.as_path()))?;
+
+    Ok(content.lines().next().unwrap_or("").to_string())
+}
+
+/// Take the password from the password file (~/.pgpass by default) unless given
+fn with_passfile(params: ConnectParams, passfile: Option<&Path>) -> Result<ConnectParams, Error> {
+    let user = match params.user() {
+        Some(user) if user.password().is_none() => user.name().to_string(),
+        _ => return Ok(params),
+    };
+
+    let path = match passfile.map(Path::to_path_buf).or_else(passfile::default_path) {
+        Some(path) => path,
+        None => return Ok(params),
+    };
+
+    // as libpq does, the default unix socket matches localhost
+    let host = match *params.host() {
+        Host::Tcp(ref host) => host.as_str(),
+        Host::Unix(_) => "localhost",
+    };
+
+    let database = params.database().unwrap_or(&user);
+
+    let password = match passfile::lookup(&path, host, params.port(), database, &user)? {
+        Some(password) => password,
+        None => return Ok(params),
+    };
+
+    log::debug!("The password is taken from {:?}", path);
+
+    let mut builder = ConnectParams::builder();
+
+    builder
+        .port(params.port())
+        .user(&user, Some(&password))
+        .connect_timeout(params.connect_timeout());
+
+    if let Some(database) = params.database() {
+        builder.database(database);
+    }
+
+    for (name, value) in params.options() {
+        builder.option(name, value);
+    }
+
+    Ok(builder.build(params.host().clone()))
+}
+
+fn connect(params: ConnectParams, settings: &tls::Settings, schema: &str) -> Result<Connection, Error> {
+    if !registry::is_valid_schema(schema) {
+        Err(failure::err_msg(format!(
+            "Invalid registry schema \"{}\", it must be a lowercase identifier",
+            schema
+        )))?
+    }
+
+    let handshake = settings.handshake()?;
+
+    let connection = postgres::Connection::connect(params, settings.tls_mode(&handshake))?;
 
     connection.execute(
         "select set_config('application_name', 'warden', false)",
@@ -128,33 +708,63 @@ pub fn open(url: &str) -> Result<Connection, Error> {
         .get_opt(0)
         .ok_or(failure::err_msg("Could not fetch current catalog"))??;
 
-    let initialised: bool = connection
-        .query(
-            "select
-              count(*) = 1
-            from
-              information_schema.tables
-            where
-              table_catalog = $1
-            and
-              table_schema = 'warden'
-            and
-              table_name = 'migration'
-            ",
-            &[&catalog],
-        )?
-        .get(0)
-        .get_opt(0)
-        .ok_or(failure::err_msg("Could not read information schema"))??;
+    let initialised = is_initialised(&connection, &catalog, schema)?;
+
+    // a schema newer than the binary supports is refused right away, while
+    // an older one is only good for the upgrade (see `ensure_schema_version`)
+    let schema_version = if initialised {
+        let version = upgrade::current_version(&connection, schema)?;
+        upgrade::ensure_supported(version)?;
+        Some(version)
+    } else {
+        None
+    };
 
     Ok(Connection {
         connection: connection,
         catalog: catalog,
         initialised: initialised,
+        schema_version: schema_version,
+        schema: schema.to_string(),
     })
 }
 
-fn is_initialised(connection: &postgres::Connection, catalog: &str) -> Result<bool, Error> {
+/// Equivalent of `SET [LOCAL]` for the timeouts that are defined
+fn set_timeouts(connection: &GenericConnection, timeouts: &Timeouts, local: bool) -> Result<(), Error> {
+    for (name, value) in &[
+        ("lock_timeout", &timeouts.lock_timeout),
+        ("statement_timeout", &timeouts.statement_timeout),
+    ] {
+        if let Some(value) = value {
+            connection.execute("select set_config($1, $2, $3)", &[name, value, &local])?;
+        }
+    }
+
+    Ok(())
+}
+
+fn reset_timeouts(connection: &GenericConnection, timeouts: &Timeouts) -> Result<(), Error> {
+    if timeouts.lock_timeout.is_some() {
+        connection.batch_execute("reset lock_timeout")?;
+    }
+
+    if timeouts.statement_timeout.is_some() {
+        connection.batch_execute("reset statement_timeout")?;
+    }
+
+    Ok(())
+}
+
+fn is_lock_timeout(error: &Error) -> bool {
+    error.iter_chain().any(|cause| {
+        cause
+            .downcast_ref::<postgres::Error>()
+            .and_then(|error| error.code())
+            == Some(&postgres::error::LOCK_NOT_AVAILABLE)
+    })
+}
+
+fn is_initialised(connection: &postgres::Connection, catalog: &str, schema: &str) -> Result<bool, Error> {
     let result: bool = connection
         .query(
             "select
@@ -164,11 +774,11 @@ fn is_initialised(connection: &postgres::Connection, catalog: &str) -> Result<bo
             where
               table_catalog = $1
             and
-              table_schema = 'warden'
+              table_schema = $2
             and
               table_name = 'migration'
             ",
-            &[&catalog],
+            &[&catalog, &schema],
         )?
         .get(0)
         .get_opt(0)
@@ -176,3 +786,58 @@ fn is_initialised(connection: &postgres::Connection, catalog: &str) -> Result<bo
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use warden_core::testing;
+
+    fn password(params: &ConnectParams) -> Option<&str> {
+        params.user().and_then(|user| user.password())
+    }
+
+    #[test]
+    fn test_read_password_file() {
+        let root = testing::temp_dir("postgres-password-file");
+        let file = testing::private_file(&root, "password", "s3cr:et \nsecond line\n");
+        let path = PathArc::new(&file);
+
+        assert_eq!(read_password_file(&path).unwrap(), "s3cr:et ");
+
+        file.write_str("").unwrap();
+        assert_eq!(read_password_file(&path).unwrap(), "");
+
+        fs::remove_file(&file).unwrap();
+        assert!(read_password_file(&path).is_err());
+
+        root.remove_all().unwrap();
+    }
+
+    #[test]
+    fn test_with_passfile() {
+        let root = testing::temp_dir("postgres-passfile");
+        let file = testing::private_file(&root, "pgpass", "db:5432:app:deploy:remote\nlocalhost:5433:*:deploy:local\n");
+        let path = file.as_path();
+
+        let params = with_passfile("postgres://deploy@db/app".into_connect_params().unwrap(), Some(path)).unwrap();
+        assert_eq!(password(&params), Some("remote"));
+        assert_eq!(params.database(), Some("app"));
+
+        // the default unix socket matches localhost, the database defaults to the user name
+        let params = ConnectParams::builder()
+            .port(5433)
+            .user("deploy", None)
+            .build(Host::Unix(PathBuf::from("/var/run/postgresql")));
+        assert_eq!(password(&with_passfile(params, Some(path)).unwrap()), Some("local"));
+
+        // the password given is kept
+        let params = "postgres://deploy:given@db/app".into_connect_params().unwrap();
+        assert_eq!(password(&with_passfile(params, Some(path)).unwrap()), Some("given"));
+
+        let params = "postgres://admin@db/app".into_connect_params().unwrap();
+        assert_eq!(password(&with_passfile(params, Some(path)).unwrap()), None);
+
+        root.remove_all().unwrap();
+    }
+}
@@ -1,9 +1,10 @@
 use failure::Error;
 use libflate::gzip::Decoder;
 use path_abs::PathDir;
+use postgres::error::CANNOT_CONNECT_NOW;
 use tar;
 use warden_core::dbms::driver::{Driver, DriverFactory};
-use warden_core::dbms::Connection;
+use warden_core::dbms::{Connection, ConnectionParams, Tls};
 
 static NAME: &'static str = "postgresql";
 
@@ -16,8 +17,30 @@ impl Driver for PostgreSQL {
         Ok(tar::Archive::new(Decoder::new(crate::zero_migration_tar())?).unpack(folder)?)
     }
 
-    fn open_connection(&self, url: &str) -> Result<Box<Connection>, Error> {
-        Ok(Box::new(super::connection::open(url)?))
+    fn open_connection(&self, url: &str, registry_schema: &str) -> Result<Box<Connection>, Error> {
+        Ok(Box::new(super::connection::open(url, registry_schema, &Tls::default())?))
+    }
+
+    /// The server is not listening yet (or the socket is missing), or it is starting up
+    fn is_transient_error(&self, error: &Error) -> bool {
+        match error.downcast_ref::<postgres::Error>() {
+            Some(error) if error.as_io().is_some() => true,
+            Some(error) => error.code() == Some(&CANNOT_CONNECT_NOW),
+            None => false,
+        }
+    }
+
+    fn open_configured_connection(
+        &self,
+        url: Option<&str>,
+        params: &ConnectionParams,
+        tls: &Tls,
+        registry_schema: &str,
+    ) -> Result<Box<Connection>, Error> {
+        match url {
+            Some(url) => Ok(Box::new(super::connection::open(url, registry_schema, tls)?)),
+            None => Ok(Box::new(super::connection::open_params(params, registry_schema, tls)?)),
+        }
     }
 }
 
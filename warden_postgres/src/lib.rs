@@ -1,6 +1,12 @@
 mod api;
 pub mod driver;
 pub mod connection;
+mod lock;
+mod passfile;
+mod registry;
+pub mod statement;
+pub mod tls;
+mod upgrade;
 
 pub fn zero_migration_tar() -> &'static [u8] {
     let _archive = include_bytes!(concat!(env!("OUT_DIR"), "/000000--warden-init.tar.gz"));
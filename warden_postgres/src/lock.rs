@@ -0,0 +1,198 @@
+//! Session level advisory lock guarding the warden schema
+//! from concurrent modifications
+
+use failure::Error;
+use postgres;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The first half of the advisory lock key ("ward")
+const LOCK_CLASS: i32 = 0x7761_7264;
+
+/// How often to retry while waiting for the lock with a timeout
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The second half of the advisory lock key, derived from the schema name
+/// (32 bit FNV-1a, non-negative to be the same for the `oid` of `pg_locks`)
+fn key(schema: &str) -> i32 {
+    let hash = schema
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193));
+
+    (hash & 0x7fff_ffff) as i32
+}
+
+/// Retry the attempt every interval until it succeeds or the timeout expires.
+/// Returns whether it has succeeded
+fn retry<F>(timeout: Duration, interval: Duration, mut attempt: F) -> Result<bool, Error>
+where
+    F: FnMut() -> Result<bool, Error>,
+{
+    let started = Instant::now();
+
+    while started.elapsed() < timeout {
+        thread::sleep(interval);
+
+        if attempt()? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Acquire the lock keyed on the schema.
+/// Waits indefinitely when no timeout given, otherwise gives up once
+/// the timeout expires (zero timeout means to not wait at all)
+pub fn acquire(
+    connection: &postgres::Connection,
+    schema: &str,
+    timeout: Option<Duration>,
+) -> Result<(), Error> {
+    if try_acquire(connection, schema)? {
+        return Ok(());
+    }
+
+    let holder = describe_holder(connection, schema)?;
+
+    let timeout = if let Some(timeout) = timeout {
+        timeout
+    } else {
+        log::info!("Waiting for the lock held by {}", holder);
+
+        connection.execute("select pg_advisory_lock($1, $2)", &[&LOCK_CLASS, &key(schema)])?;
+
+        return Ok(());
+    };
+
+    if timeout > Duration::from_secs(0) {
+        log::info!("Waiting for the lock held by {}", holder);
+
+        if retry(timeout, RETRY_INTERVAL, || try_acquire(connection, schema))? {
+            return Ok(());
+        }
+    }
+
+    Err(failure::err_msg(format!(
+        "Could not acquire the lock on the \"{}\" schema, it is held by {}",
+        schema,
+        describe_holder(connection, schema)?
+    )))
+}
+
+pub fn release(connection: &postgres::Connection, schema: &str) -> Result<(), Error> {
+    connection.execute("select pg_advisory_unlock($1, $2)", &[&LOCK_CLASS, &key(schema)])?;
+
+    Ok(())
+}
+
+fn try_acquire(connection: &postgres::Connection, schema: &str) -> Result<bool, Error> {
+    let result: bool = connection
+        .query("select pg_try_advisory_lock($1, $2)", &[&LOCK_CLASS, &key(schema)])?
+        .get(0)
+        .get_opt(0)
+        .ok_or(failure::err_msg("Could not acquire the lock"))??;
+
+    Ok(result)
+}
+
+/// Human readable description of the backend holding the lock
+fn describe_holder(connection: &postgres::Connection, schema: &str) -> Result<String, Error> {
+    let rows = connection.query(
+        "select
+          a.pid,
+          coalesce(a.application_name, ''),
+          coalesce(a.usename::text, ''),
+          coalesce(host(a.client_addr), 'local')
+        from
+          pg_locks l
+        join
+          pg_stat_activity a on a.pid = l.pid
+        where
+          l.locktype = 'advisory'
+        and
+          l.granted
+        and
+          l.database = (select oid from pg_database where datname = current_database())
+        and
+          l.classid = $1::int8::oid
+        and
+          l.objid = $2::int8::oid
+        and
+          l.objsubid = 2
+        ",
+        &[&i64::from(LOCK_CLASS), &i64::from(key(schema))],
+    )?;
+
+    let row = if let Some(row) = rows.iter().next() {
+        row
+    } else {
+        return Ok("an unknown backend".to_string());
+    };
+
+    let missing = || failure::err_msg("Could not fetch the lock holder");
+
+    let pid: i32 = row.get_opt(0).ok_or_else(missing)??;
+    let application: String = row.get_opt(1).ok_or_else(missing)??;
+    let user: String = row.get_opt(2).ok_or_else(missing)??;
+    let address: String = row.get_opt(3).ok_or_else(missing)??;
+
+    Ok(format!(
+        "backend pid {} (application \"{}\", user \"{}\", client {})",
+        pid, application, user, address
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key() {
+        assert_eq!(key("warden"), key("warden"));
+        assert_ne!(key("warden"), key("warden_test"));
+        assert_eq!(key(""), 0x011c_9dc5);
+        assert_eq!(key("a"), 0x640c_292c);
+
+        for schema in &["warden", "public", "Warden", "a very long schema name to hash"] {
+            assert!(key(schema) >= 0);
+        }
+    }
+
+    #[test]
+    fn test_retry() {
+        let interval = Duration::from_millis(1);
+
+        let mut attempts = 0;
+        assert!(!retry(Duration::from_secs(0), interval, || {
+            attempts += 1;
+            Ok(true)
+        })
+        .unwrap());
+        assert_eq!(attempts, 0);
+
+        let mut attempts = 0;
+        assert!(retry(Duration::from_secs(5), interval, || {
+            attempts += 1;
+            Ok(attempts == 3)
+        })
+        .unwrap());
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        assert!(!retry(Duration::from_millis(20), interval, || {
+            attempts += 1;
+            Ok(false)
+        })
+        .unwrap());
+        assert!(attempts > 0);
+
+        let mut attempts = 0;
+        assert!(retry(Duration::from_secs(5), interval, || {
+            attempts += 1;
+            Err(failure::err_msg("connection lost"))
+        })
+        .is_err());
+        assert_eq!(attempts, 1);
+    }
+}
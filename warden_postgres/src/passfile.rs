@@ -0,0 +1,148 @@
+//! The password file of libpq (`~/.pgpass`), one `hostname:port:database:username:password`
+//! line per connection, where any field but the password may be `*` to match anything.
+//! Colons and backslashes within the fields are escaped with a backslash.
+
+use failure::{Error, ResultExt};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The file named by PGPASSFILE, ~/.pgpass otherwise
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("PGPASSFILE") {
+        return Some(PathBuf::from(path));
+    }
+
+    dirs::home_dir().map(|home| home.join(".pgpass"))
+}
+
+/// The password of the first line matching the connection.
+/// Missing files are no error, as well as the files readable by others (which are ignored)
+pub fn lookup(path: &Path, host: &str, port: u16, database: &str, user: &str) -> Result<Option<String>, Error> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    if !is_private(path)? {
+        log::warn!(
+            "The password file {:?} has group or world access, permissions should be u=rw (0600) or less",
+            path
+        );
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path).with_context(|_| format!("Could not read the password file {:?}", path))?;
+    let port = port.to_string();
+
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let fields = split(line);
+
+        if fields.len() != 5 {
+            continue;
+        }
+
+        let matches = [host, &port, database, user]
+            .iter()
+            .zip(&fields)
+            .all(|(value, field)| field == "*" || field == value);
+
+        if matches {
+            return Ok(Some(fields[4].clone()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Split the line by the colons, unescaping the fields.
+/// As with libpq, the password ends at the first unescaped colon (the rest is ignored)
+fn split(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    fields.last_mut().unwrap().push(c);
+                }
+            }
+            ':' if fields.len() < 5 => fields.push(String::new()),
+            ':' => break,
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    fields
+}
+
+#[cfg(unix)]
+fn is_private(path: &Path) -> Result<bool, Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Ok(fs::metadata(path)?.permissions().mode() & 0o077 == 0)
+}
+
+#[cfg(not(unix))]
+fn is_private(_path: &Path) -> Result<bool, Error> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warden_core::testing;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("localhost:5432:app:deploy:secret"), vec!["localhost", "5432", "app", "deploy", "secret"]);
+        assert_eq!(split(r"*:*:a\:b:us\\er:pass:word"), vec!["*", "*", "a:b", r"us\er", "pass"]);
+        assert_eq!(split(r"*:*:*:*:pass\:word:ignored"), vec!["*", "*", "*", "*", "pass:word"]);
+        assert_eq!(split("localhost:5432").len(), 2);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let root = testing::temp_dir("pgpass-lookup");
+        let lines = [
+            "# comment:*:*:*:nope",
+            "db:5432:app:deploy:first",
+            "db:5432:app:deploy:second",
+            "malformed:line",
+            r"*:5433:a\:b:*:any\:user",
+            "localhost:*:*:deploy:local",
+        ];
+        let file = testing::private_file(&root, "pgpass", &lines.join("\n"));
+
+        let lookup = |host, port, database, user| lookup(&file, host, port, database, user).unwrap();
+
+        assert_eq!(lookup("db", 5432, "app", "deploy"), Some("first".to_string()));
+        assert_eq!(lookup("other", 5433, "a:b", "someone"), Some("any:user".to_string()));
+        assert_eq!(lookup("localhost", 5432, "app", "deploy"), Some("local".to_string()));
+        assert_eq!(lookup("db", 5432, "app", "admin"), None);
+        assert_eq!(lookup("comment", 1, "*", "*"), None);
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(lookup("db", 5432, "app", "deploy"), None);
+
+        root.remove_all().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lookup_public() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = testing::temp_dir("pgpass-public");
+        let file = testing::private_file(&root, "pgpass", "*:*:*:*:secret");
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+
+        assert_eq!(lookup(&file, "db", 5432, "app", "deploy").unwrap(), None);
+
+        root.remove_all().unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+//! Rendering the warden SQL against the configured registry schema.
+//!
+//! The initial migration, the upgrades and the API calls are all written
+//! against the `warden` schema, which gets replaced with the configured one
+//! right before the SQL is sent to the database.
+
+use std::borrow::Cow;
+
+/// The schema name the SQL is written against
+const CANONICAL: &str = "warden";
+
+/// Replace the canonical schema name with the given one.
+/// Only the schema qualified names (`warden.name`) and the
+/// schema definitions (`schema warden`) are replaced.
+pub fn render<'a>(sql: &'a str, schema: &str) -> Cow<'a, str> {
+    if schema == CANONICAL {
+        return Cow::Borrowed(sql);
+    }
+
+    let bytes = sql.as_bytes();
+    let mut result = String::with_capacity(sql.len());
+    let mut start = 0;
+
+    for (pos, _) in sql.match_indices(CANONICAL) {
+        let end = pos + CANONICAL.len();
+
+        if pos > 0 && is_ident_char(bytes[pos - 1]) {
+            continue;
+        }
+
+        let qualifies = bytes.get(end) == Some(&b'.');
+        let defines = sql[..pos].ends_with("schema ")
+            && bytes.get(end).map(|c| !is_ident_char(*c)).unwrap_or(true);
+
+        if qualifies || defines {
+            result.push_str(&sql[start..pos]);
+            result.push_str(schema);
+            start = end;
+        }
+    }
+
+    if start == 0 {
+        return Cow::Borrowed(sql);
+    }
+
+    result.push_str(&sql[start..]);
+
+    Cow::Owned(result)
+}
+
+/// Whether the name can be used as the schema name without quoting
+pub fn is_valid_schema(name: &str) -> bool {
+    let bytes = name.as_bytes();
+
+    !bytes.is_empty()
+        && bytes.len() < 64
+        && !bytes[0].is_ascii_digit()
+        && bytes.iter().all(|c| is_ident_char(*c) && !c.is_ascii_uppercase())
+}
+
+fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_canonical() {
+        let sql = "select warden.do_log('%s', $1)";
+
+        assert_eq!(render(sql, "warden"), sql);
+    }
+
+    #[test]
+    fn test_render_qualified() {
+        assert_eq!(
+            render("select warden.do_log('warden %s', $1) from my_warden.x", "app_registry"),
+            "select app_registry.do_log('warden %s', $1) from my_warden.x"
+        );
+    }
+
+    #[test]
+    fn test_render_definition() {
+        assert_eq!(
+            render(
+                "create schema warden;\ncomment on schema warden is 'Warden data';\n-- /warden/migration.sql\n",
+                "app_registry"
+            ),
+            "create schema app_registry;\ncomment on schema app_registry is 'Warden data';\n-- /warden/migration.sql\n"
+        );
+    }
+
+    #[test]
+    fn test_valid_schema() {
+        assert!(is_valid_schema("warden"));
+        assert!(is_valid_schema("_app_1"));
+        assert!(!is_valid_schema(""));
+        assert!(!is_valid_schema("1app"));
+        assert!(!is_valid_schema("App"));
+        assert!(!is_valid_schema("app; drop table x"));
+    }
+}
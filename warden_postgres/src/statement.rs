@@ -0,0 +1,200 @@
+//! Splitting SQL scripts into separate statements, so that they
+//! can be executed one by one from the client
+
+/// Split the SQL into statements by the top level semicolons,
+/// taking into account string literals, quoted identifiers,
+/// comments and dollar quoting.
+/// Statements consisting of comments only are left out.
+pub fn split(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut result = Vec::new();
+
+    let mut start = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        pos = match bytes[pos] {
+            b'\'' => {
+                let escapes = pos > 0
+                    && (bytes[pos - 1] == b'E' || bytes[pos - 1] == b'e')
+                    && (pos < 2 || !is_ident_char(bytes[pos - 2]));
+                skip_quoted(bytes, pos, b'\'', escapes)
+            }
+            b'"' => skip_quoted(bytes, pos, b'"', false),
+            b'-' if bytes.get(pos + 1) == Some(&b'-') => skip_line_comment(bytes, pos),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => skip_block_comment(bytes, pos),
+            b'$' if pos == 0 || !is_ident_char(bytes[pos - 1]) => skip_dollar_quoted(sql, pos),
+            b';' => {
+                push_statement(&mut result, &sql[start..pos]);
+                start = pos + 1;
+                pos + 1
+            }
+            _ => pos + 1,
+        };
+    }
+
+    if start < bytes.len() {
+        push_statement(&mut result, &sql[start..]);
+    }
+
+    result
+}
+
+/// Human readable position of the statement (a slice of the source)
+/// such as "statement 2 (line 14)"
+pub fn describe(source: &str, statement: &str, number: usize) -> String {
+    let offset = statement.as_ptr() as usize - source.as_ptr() as usize;
+    let line = source[..offset].matches('\n').count() + 1;
+
+    format!("statement {} (line {})", number, line)
+}
+
+fn push_statement<'a>(result: &mut Vec<&'a str>, statement: &'a str) {
+    let statement = statement.trim();
+
+    if !is_blank(statement) {
+        result.push(statement);
+    }
+}
+
+/// Whether the statement consists of comments and whitespaces only
+fn is_blank(statement: &str) -> bool {
+    let bytes = statement.as_bytes();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        pos = match bytes[pos] {
+            b'-' if bytes.get(pos + 1) == Some(&b'-') => skip_line_comment(bytes, pos),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => skip_block_comment(bytes, pos),
+            c if c.is_ascii_whitespace() => pos + 1,
+            _ => return false,
+        };
+    }
+
+    true
+}
+
+fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80
+}
+
+/// Returns the position right after the closing quote
+fn skip_quoted(bytes: &[u8], pos: usize, quote: u8, escapes: bool) -> usize {
+    let mut pos = pos + 1;
+
+    while pos < bytes.len() {
+        if escapes && bytes[pos] == b'\\' {
+            pos += 2;
+        } else if bytes[pos] == quote {
+            if bytes.get(pos + 1) == Some(&quote) {
+                pos += 2;
+            } else {
+                return pos + 1;
+            }
+        } else {
+            pos += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+fn skip_line_comment(bytes: &[u8], pos: usize) -> usize {
+    match bytes[pos..].iter().position(|c| *c == b'\n') {
+        Some(offset) => pos + offset + 1,
+        None => bytes.len(),
+    }
+}
+
+/// Block comments may be nested in PostgreSQL
+fn skip_block_comment(bytes: &[u8], pos: usize) -> usize {
+    let mut depth = 0;
+    let mut pos = pos;
+
+    while pos < bytes.len() {
+        if bytes[pos] == b'/' && bytes.get(pos + 1) == Some(&b'*') {
+            depth += 1;
+            pos += 2;
+        } else if bytes[pos] == b'*' && bytes.get(pos + 1) == Some(&b'/') {
+            depth -= 1;
+            pos += 2;
+
+            if depth == 0 {
+                return pos;
+            }
+        } else {
+            pos += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+/// Skips `$tag$ ... $tag$`, or a single `$` if it does not open a dollar quote
+/// (e.g. a positional parameter such as `$1`)
+fn skip_dollar_quoted(sql: &str, pos: usize) -> usize {
+    let bytes = sql.as_bytes();
+
+    let tag_len = bytes[pos + 1..]
+        .iter()
+        .position(|c| !is_ident_char(*c))
+        .unwrap_or(bytes.len() - pos - 1);
+
+    let tag_end = pos + 1 + tag_len;
+
+    if bytes.get(tag_end) != Some(&b'$') || (tag_len > 0 && bytes[pos + 1].is_ascii_digit()) {
+        return pos + 1;
+    }
+
+    let tag = &sql[pos..=tag_end];
+
+    match sql[tag_end + 1..].find(tag) {
+        Some(offset) => tag_end + 1 + offset + tag.len(),
+        None => bytes.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_simple() {
+        assert_eq!(
+            split("create table a (id int);\n\ncreate table b (id int)\n"),
+            vec!["create table a (id int)", "create table b (id int)"]
+        );
+    }
+
+    #[test]
+    fn test_split_comments() {
+        assert_eq!(
+            split("-- one; two\n/* three; /* four; */ five; */ select 1; -- six;\n"),
+            vec!["-- one; two\n/* three; /* four; */ five; */ select 1"]
+        );
+    }
+
+    #[test]
+    fn test_split_literals() {
+        assert_eq!(
+            split(r#"select 'a;''b'; select "c;""d"; select E'e\';f'; select $1;"#),
+            vec![
+                "select 'a;''b'",
+                r#"select "c;""d""#,
+                r"select E'e\';f'",
+                "select $1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_dollar_quoted() {
+        let function = "create function f() returns void as $$ begin perform 1; end; $$ language plpgsql";
+        let tagged = "do $body$ begin execute $$select 1;$$; end; $body$";
+
+        assert_eq!(
+            split(&format!("{};\n{};\nselect 2", function, tagged)),
+            vec![function, tagged, "select 2"]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! TLS connections, configured the way libpq does it: with the `sslmode`,
+//! `sslrootcert`, `sslcert` and `sslkey` parameters of the connection URL,
+//! falling back to the same settings of the config file.
+//! The values of the URL are percent-decoded (e.g. `sslrootcert=%2Fetc%2Froot%20ca.crt`).
+//!
+//! The mode is `prefer` by default. The configs of the version 0.1 keep TLS
+//! disabled unless `sslmode` is given, as it has been before TLS was supported.
+//!
+//! As with libpq, `prefer` and `require` do not verify the server certificate
+//! unless the root certificate is given, `verify-ca` checks the certificate
+//! is signed by a trusted authority (the root certificate or the system ones)
+//! and `verify-full` checks the host name matches the certificate as well.
+
+use failure::Error;
+use openssl::ssl::{SslConnector, SslFiletype, SslMethod, SslStream, SslVerifyMode};
+use postgres::tls::{self, TlsHandshake, TlsStream};
+use postgres::TlsMode;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use warden_core::dbms::Tls;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn from_str(mode: &str) -> Result<SslMode, Error> {
+        match mode {
+            "disable" => Ok(SslMode::Disable),
+            "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            _ => Err(failure::err_msg(format!(
+                "Invalid sslmode \"{}\", expected disable, prefer, require, verify-ca or verify-full",
+                mode
+            ))),
+        }
+    }
+}
+
+/// The TLS settings of a connection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub mode: SslMode,
+    pub root_cert: Option<PathBuf>,
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+impl Settings {
+    /// The settings of the config file, `prefer` by default
+    pub fn from_config(tls: &Tls) -> Result<Settings, Error> {
+        let mode = match tls.mode {
+            Some(ref mode) => SslMode::from_str(mode)?,
+            None => SslMode::Prefer,
+        };
+
+        Ok(Settings {
+            mode: mode,
+            root_cert: tls.root_cert.as_ref().map(|path| path.to_path_buf()),
+            cert: tls.cert.as_ref().map(|path| path.to_path_buf()),
+            key: tls.key.as_ref().map(|path| path.to_path_buf()),
+        })
+    }
+
+    /// Split the TLS parameters off the URL (the server would take them for its own),
+    /// taking the missing ones from the config file.
+    /// Returns the URL without the parameters along with the settings
+    pub fn parse(url: &str, tls: &Tls) -> Result<(String, Settings), Error> {
+        let mut settings = Settings::from_config(tls)?;
+
+        let (base, query) = match url.find('?') {
+            Some(pos) => (&url[..pos], &url[pos + 1..]),
+            None => return Ok((url.to_string(), settings)),
+        };
+
+        let mut rest = Vec::new();
+
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = match param.find('=') {
+                Some(pos) => (&param[..pos], &param[pos + 1..]),
+                None => (param, ""),
+            };
+
+            match key {
+                "sslmode" => settings.mode = SslMode::from_str(&percent_decode(value)?)?,
+                "sslrootcert" => settings.root_cert = Some(PathBuf::from(percent_decode(value)?)),
+                "sslcert" => settings.cert = Some(PathBuf::from(percent_decode(value)?)),
+                "sslkey" => settings.key = Some(PathBuf::from(percent_decode(value)?)),
+                _ => rest.push(param),
+            }
+        }
+
+        let url = if rest.is_empty() {
+            base.to_string()
+        } else {
+            format!("{}?{}", base, rest.join("&"))
+        };
+
+        Ok((url, settings))
+    }
+
+    /// The handshake to connect with, None if TLS is disabled
+    pub fn handshake(&self) -> Result<Option<Handshake>, Error> {
+        if self.mode == SslMode::Disable {
+            return Ok(None);
+        }
+
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+
+        if let Some(ref root_cert) = self.root_cert {
+            builder
+                .set_ca_file(root_cert)
+                .map_err(|error| file_error("root certificate", root_cert, error))?;
+        }
+
+        match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => {
+                builder
+                    .set_certificate_chain_file(cert)
+                    .map_err(|error| file_error("client certificate", cert, error))?;
+                builder
+                    .set_private_key_file(key, SslFiletype::PEM)
+                    .map_err(|error| file_error("client key", key, error))?;
+                builder.check_private_key()?;
+            }
+            (None, None) => (),
+            _ => Err(failure::err_msg(
+                "The client certificate (sslcert) and its key (sslkey) must be given together",
+            ))?,
+        }
+
+        let verify_ca = match self.mode {
+            SslMode::VerifyCa | SslMode::VerifyFull => true,
+            _ => self.root_cert.is_some(),
+        };
+
+        if !verify_ca {
+            builder.set_verify(SslVerifyMode::NONE);
+        }
+
+        Ok(Some(Handshake {
+            connector: builder.build(),
+            verify_hostname: self.mode == SslMode::VerifyFull,
+        }))
+    }
+
+    /// The mode to pass to the driver along with the handshake
+    pub fn tls_mode<'a>(&self, handshake: &'a Option<Handshake>) -> TlsMode<'a> {
+        match (self.mode, handshake) {
+            (SslMode::Disable, _) | (_, None) => TlsMode::None,
+            (SslMode::Prefer, Some(handshake)) => TlsMode::Prefer(handshake),
+            (_, Some(handshake)) => TlsMode::Require(handshake),
+        }
+    }
+}
+
+/// The value of the URL with the `%XX` sequences decoded
+fn percent_decode(value: &str) -> Result<String, Error> {
+    let invalid = || failure::err_msg(format!("Invalid percent-encoding of \"{}\" in the connection URL", value));
+
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2).ok_or_else(invalid)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+fn file_error(what: &str, path: &PathBuf, error: openssl::error::ErrorStack) -> Error {
+    failure::err_msg(format!("Could not load the {} {:?}: {}", what, path, error))
+}
+
+pub struct Handshake {
+    connector: SslConnector,
+    verify_hostname: bool,
+}
+
+impl fmt::Debug for Handshake {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Handshake")
+            .field("verify_hostname", &self.verify_hostname)
+            .finish()
+    }
+}
+
+impl TlsHandshake for Handshake {
+    fn tls_handshake(
+        &self,
+        host: &str,
+        stream: tls::Stream,
+    ) -> Result<Box<TlsStream>, Box<std::error::Error + Sync + Send>> {
+        let stream = self
+            .connector
+            .configure()?
+            .verify_hostname(self.verify_hostname)
+            .connect(host, stream)
+            .map_err(|error| error.to_string())?;
+
+        Ok(Box::new(Stream(stream)))
+    }
+}
+
+#[derive(Debug)]
+struct Stream(SslStream<tls::Stream>);
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl TlsStream for Stream {
+    fn get_ref(&self) -> &tls::Stream {
+        self.0.get_ref()
+    }
+
+    fn get_mut(&mut self) -> &mut tls::Stream {
+        self.0.get_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use path_abs::PathArc;
+    use std::env;
+
+    /// The self-signed certificates (the root one and the one of `localhost` signed by it)
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("db/fixtures/tls").join(name)
+    }
+
+    fn settings(mode: SslMode, root_cert: Option<&str>, cert: Option<&str>, key: Option<&str>) -> Settings {
+        Settings {
+            mode: mode,
+            root_cert: root_cert.map(fixture),
+            cert: cert.map(fixture),
+            key: key.map(fixture),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let tls = Tls {
+            mode: Some("require".to_string()),
+            root_cert: Some(PathArc::new("/etc/root.crt")),
+            cert: None,
+            key: None,
+        };
+
+        let (url, settings) = Settings::parse("postgres://localhost/app", &tls).unwrap();
+        assert_eq!(url, "postgres://localhost/app");
+        assert_eq!(settings.mode, SslMode::Require);
+        assert_eq!(settings.root_cert, Some(PathBuf::from("/etc/root.crt")));
+
+        let (url, settings) = Settings::parse(
+            "postgres://localhost/app?sslmode=verify-full&application_name=x&sslcert=a.crt&sslkey=a.key",
+            &tls,
+        )
+        .unwrap();
+        assert_eq!(url, "postgres://localhost/app?application_name=x");
+        assert_eq!(settings.mode, SslMode::VerifyFull);
+        assert_eq!(settings.cert, Some(PathBuf::from("a.crt")));
+        assert_eq!(settings.key, Some(PathBuf::from("a.key")));
+
+        let (url, settings) = Settings::parse("postgres://localhost/app?sslmode=disable", &Tls::default()).unwrap();
+        assert_eq!(url, "postgres://localhost/app");
+        assert_eq!(settings.mode, SslMode::Disable);
+
+        assert_eq!(Settings::parse("postgres://localhost/app", &Tls::default()).unwrap().1.mode, SslMode::Prefer);
+        assert!(Settings::parse("postgres://localhost/app?sslmode=allow", &Tls::default()).is_err());
+
+        let (url, settings) = Settings::parse(
+            "postgres://localhost/app?sslrootcert=%2Fetc%2Froot%20ca.crt&sslcert=caf%C3%A9.crt&sslkey=a%2bb.key&sslmode=verify%2Dca",
+            &Tls::default(),
+        )
+        .unwrap();
+        assert_eq!(url, "postgres://localhost/app");
+        assert_eq!(settings.mode, SslMode::VerifyCa);
+        assert_eq!(settings.root_cert, Some(PathBuf::from("/etc/root ca.crt")));
+        assert_eq!(settings.cert, Some(PathBuf::from("caf\u{e9}.crt")));
+        assert_eq!(settings.key, Some(PathBuf::from("a+b.key")));
+
+        assert!(Settings::parse("postgres://localhost/app?sslrootcert=a%2", &Tls::default()).is_err());
+        assert!(Settings::parse("postgres://localhost/app?sslrootcert=a%zz", &Tls::default()).is_err());
+        assert!(Settings::parse("postgres://localhost/app?sslrootcert=%ff", &Tls::default()).is_err());
+    }
+
+    #[test]
+    fn test_handshake() {
+        assert!(settings(SslMode::Disable, None, None, None).handshake().unwrap().is_none());
+
+        let handshake = settings(SslMode::VerifyFull, Some("root.crt"), Some("server.crt"), Some("server.key"))
+            .handshake()
+            .unwrap()
+            .unwrap();
+        assert!(handshake.verify_hostname);
+
+        let handshake = settings(SslMode::Prefer, None, None, None).handshake().unwrap();
+        assert!(!handshake.unwrap().verify_hostname);
+
+        // the certificate does not match the key
+        assert!(settings(SslMode::Require, None, Some("root.crt"), Some("server.key")).handshake().is_err());
+
+        assert!(settings(SslMode::Require, None, Some("server.crt"), None).handshake().is_err());
+        assert!(settings(SslMode::Require, Some("missing.crt"), None, None).handshake().is_err());
+    }
+
+    /// Connects to the server with TLS configured with the certificates of the fixtures
+    /// (`ssl_cert_file` = `server.crt`, `ssl_key_file` = `server.key`), given in
+    /// `WARDEN_TEST_POSTGRES_TLS_URL` (e.g. `postgres://postgres@localhost/postgres`)
+    #[test]
+    fn test_connect() {
+        let url = match env::var("WARDEN_TEST_POSTGRES_TLS_URL") {
+            Ok(url) => url,
+            Err(_) => return,
+        };
+
+        let tls = Tls {
+            mode: Some("verify-full".to_string()),
+            root_cert: Some(PathArc::new(fixture("root.crt"))),
+            cert: None,
+            key: None,
+        };
+
+        let (url, settings) = Settings::parse(&url, &tls).unwrap();
+        let handshake = settings.handshake().unwrap();
+
+        let connection = postgres::Connection::connect(url.as_str(), settings.tls_mode(&handshake)).unwrap();
+
+        let rows = connection
+            .query("select ssl from pg_stat_ssl where pid = pg_backend_pid()", &[])
+            .unwrap();
+        assert!(rows.get(0).get::<_, bool>(0));
+
+        // the certificate is not trusted without the root one
+        let settings = Settings {
+            root_cert: None,
+            ..settings
+        };
+        let handshake = settings.handshake().unwrap();
+        assert!(postgres::Connection::connect(url.as_str(), settings.tls_mode(&handshake)).is_err());
+    }
+}
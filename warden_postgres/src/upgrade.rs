@@ -0,0 +1,199 @@
+//! Versioned upgrades of the warden schema.
+//!
+//! The initial migration creates the schema of version 1 and stays
+//! frozen since then, so any further change to the warden schema must
+//! come as an upgrade here. The databases initialised before the versioning
+//! was introduced have no version recorded and are considered as version 0.
+
+use failure::Error;
+use postgres::transaction::Transaction;
+use postgres::GenericConnection;
+
+use crate::registry::render;
+
+pub struct Upgrade {
+    /// The schema version after the upgrade is applied
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+pub static UPGRADES: &[Upgrade] = &[Upgrade {
+    version: 1,
+    name: "0001--versioned-schema",
+    sql: include_str!("../db/upgrades/0001--versioned-schema.sql"),
+}];
+
+/// The schema version the binary expects
+pub fn latest_version() -> i32 {
+    UPGRADES.last().map(|upgrade| upgrade.version).unwrap_or(0)
+}
+
+/// Make sure the binary knows the schema version
+pub fn ensure_supported(version: i32) -> Result<(), Error> {
+    if version > latest_version() {
+        Err(failure::err_msg(format!(
+            "The warden schema is at version {} which is newer than this warden supports ({}), please upgrade warden",
+            version,
+            latest_version()
+        )))?
+    }
+
+    Ok(())
+}
+
+/// The version of the warden schema in the database
+pub fn current_version(connection: &GenericConnection, schema: &str) -> Result<i32, Error> {
+    let versioned: bool = connection
+        .query(
+            "select
+              count(*) = 1
+            from
+              information_schema.tables
+            where
+              table_catalog = current_database()
+            and
+              table_schema = $1
+            and
+              table_name = 'schema_version'
+            ",
+            &[&schema],
+        )?
+        .get(0)
+        .get_opt(0)
+        .ok_or(failure::err_msg("Could not read information schema"))??;
+
+    if !versioned {
+        return Ok(0);
+    }
+
+    let version: Option<i32> = connection
+        .query(&render("select max(version) from warden.schema_version", schema), &[])?
+        .get(0)
+        .get_opt(0)
+        .ok_or(failure::err_msg("Could not fetch the warden schema version"))??;
+
+    Ok(version.unwrap_or(0))
+}
+
+/// Apply the upgrades following the current version.
+/// Returns the names of the applied ones
+pub fn apply(transaction: &Transaction, schema: &str) -> Result<Vec<&'static str>, Error> {
+    let current = current_version(transaction, schema)?;
+    ensure_supported(current)?;
+
+    let mut applied = Vec::new();
+
+    for upgrade in UPGRADES.iter().filter(|upgrade| upgrade.version > current) {
+        log::debug!("Applying the warden schema upgrade {}", upgrade.name);
+
+        transaction.batch_execute(&render(upgrade.sql, schema))?;
+        transaction.execute(
+            &render(
+                "insert into warden.schema_version (version) values ($1) on conflict do nothing",
+                schema,
+            ),
+            &[&upgrade.version],
+        )?;
+        transaction.execute(
+            &render("select warden.do_log('Upgraded the warden schema | %s', $1)", schema),
+            &[&upgrade.name],
+        )?;
+
+        applied.push(upgrade.name);
+    }
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use postgres::{Connection, TlsMode};
+    use std::env;
+
+    /// The initial migration of the releases preceding the versioning
+    const UNVERSIONED_INIT: &str = include_str!("../db/fixtures/unversioned-init.sql");
+
+    /// The current initial migration
+    const INIT: &str = include_str!("../db/migrations/000000--warden-init/migration.sql");
+
+    /// The database to test against (e.g. `postgres://postgres@localhost/warden_test`),
+    /// the tests needing it are skipped unless it is given
+    fn connect() -> Option<Connection> {
+        let url = env::var("WARDEN_TEST_POSTGRES_URL").ok()?;
+
+        Some(Connection::connect(url, TlsMode::None).unwrap())
+    }
+
+    fn reset(connection: &Connection, schema: &str) {
+        connection
+            .batch_execute(&format!("drop schema if exists {} cascade", schema))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_ensure_supported() {
+        assert!(ensure_supported(0).is_ok());
+        assert!(ensure_supported(latest_version()).is_ok());
+        assert!(ensure_supported(latest_version() + 1).is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let connection = if let Some(connection) = connect() {
+            connection
+        } else {
+            return;
+        };
+
+        let schema = "warden_test_upgrade";
+        reset(&connection, schema);
+
+        assert_eq!(current_version(&connection, schema).unwrap(), 0);
+
+        connection.batch_execute(&render(UNVERSIONED_INIT, schema)).unwrap();
+        assert_eq!(current_version(&connection, schema).unwrap(), 0);
+
+        let transaction = connection.transaction().unwrap();
+        assert_eq!(apply(&transaction, schema).unwrap(), vec!["0001--versioned-schema"]);
+        assert_eq!(current_version(&transaction, schema).unwrap(), latest_version());
+        assert!(apply(&transaction, schema).unwrap().is_empty());
+        transaction.commit().unwrap();
+
+        connection
+            .execute(
+                &render("insert into warden.schema_version (version) values ($1)", schema),
+                &[&(latest_version() + 1)],
+            )
+            .unwrap();
+
+        let transaction = connection.transaction().unwrap();
+        assert!(apply(&transaction, schema).is_err());
+        drop(transaction);
+
+        reset(&connection, schema);
+    }
+
+    #[test]
+    fn test_apply_to_init() {
+        let connection = if let Some(connection) = connect() {
+            connection
+        } else {
+            return;
+        };
+
+        let schema = "warden_test_init";
+        reset(&connection, schema);
+
+        // the current initial migration is of the latest version already
+        connection.batch_execute(&render(INIT, schema)).unwrap();
+        assert_eq!(current_version(&connection, schema).unwrap(), latest_version());
+
+        let transaction = connection.transaction().unwrap();
+        assert!(apply(&transaction, schema).unwrap().is_empty());
+        drop(transaction);
+
+        reset(&connection, schema);
+    }
+}
@@ -0,0 +1,5 @@
+use exitfailure::ExitFailure;
+
+fn main() -> Result<(), ExitFailure> {
+    Ok(warden_core::build::pack_initial_migration()?)
+}
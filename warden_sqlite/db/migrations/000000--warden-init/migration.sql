@@ -0,0 +1,147 @@
+-- ---
+-- version: 0.1
+-- ...
+-- timestamp: !!timestamp 2026-10-18T09:40:43.882+00:00
+-- manifest:
+--   - sql/warden/action_log.sql
+--   - sql/warden/lock.sql
+--   - sql/warden/migration.sql
+--   - sql/warden/migration/revert.sql
+--   - sql/warden/migration/seal.sql
+--   - sql/warden/migration/snapshot.sql
+--   - sql/warden/migration/source.sql
+--   - sql/warden/schema_version.sql
+-- ...
+
+-- BEGIN: sql/warden/action_log.sql
+
+-- The log of the actions performed by warden
+create table warden_action_log (
+  id integer not null primary key autoincrement,
+  ts text not null default (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+  -- The operating system user running warden
+  "user" text not null,
+
+  -- Always null, there are no remote clients of an embedded database
+  client_address text null,
+
+  -- The process ID of warden
+  session_pid integer not null,
+
+  the_action text
+);
+
+-- END: sql/warden/action_log.sql
+
+-- BEGIN: sql/warden/lock.sql
+
+-- The process holding the lock of the registry (the lock itself is kept on the lock file)
+create table warden_lock (
+  id integer not null primary key check (id = 1),
+
+  -- The process ID of warden
+  pid integer not null,
+
+  -- When the lock has been acquired
+  acquired_at text not null
+);
+
+-- END: sql/warden/lock.sql
+
+-- BEGIN: sql/warden/migration.sql
+
+-- Registered migrations
+create table warden_migration (
+  -- Migration ID
+  id integer not null primary key,
+
+  -- Migration name
+  name text not null,
+
+  -- Registration timestamp
+  sync_ts text not null default (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+  -- Migration deployment timestamp (null if it hasn't been deployed)
+  deploy_ts text null default null,
+
+  -- Timestamp of a failed deployment outside of a transaction (the migration may be partially applied)
+  partial_ts text null default null
+);
+
+-- END: sql/warden/migration.sql
+
+-- BEGIN: sql/warden/migration/revert.sql
+
+-- Migration revert source (SQL)
+create table warden_migration_revert (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- The SQL to be executed to revert the migration
+  data text
+);
+
+-- END: sql/warden/migration/revert.sql
+
+-- BEGIN: sql/warden/migration/seal.sql
+
+-- Migration seal (signature)
+create table warden_migration_seal (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- The seal generation timestamp (RFC 3339)
+  generated_at text not null,
+
+  -- Seal calculation algorithm
+  algo text not null,
+
+  -- Seal value. Signature generated according to the algorithm
+  value blob,
+
+  -- Seal value of the revert source (null if the migration has no revert)
+  revert_value blob null
+);
+
+-- END: sql/warden/migration/seal.sql
+
+-- BEGIN: sql/warden/migration/snapshot.sql
+
+-- Migration snapshots
+create table warden_migration_snapshot (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- Datum format (e.g. .tar.gz)
+  "format" text not null,
+
+  -- The snapshot data
+  data blob
+);
+
+-- END: sql/warden/migration/snapshot.sql
+
+-- BEGIN: sql/warden/migration/source.sql
+
+-- Migration source (SQL)
+create table warden_migration_source (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- The actual migration SQL to be executed
+  data text
+);
+
+-- END: sql/warden/migration/source.sql
+
+-- BEGIN: sql/warden/schema_version.sql
+
+-- Versions of the warden schema (the highest one is the current)
+create table warden_schema_version (
+  -- The internal schema version
+  version integer not null primary key,
+
+  -- When the schema has been upgraded to the version
+  upgrade_ts text not null default (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+insert into warden_schema_version (version) values (1);
+
+-- END: sql/warden/schema_version.sql
\ No newline at end of file
@@ -0,0 +1,16 @@
+-- The log of the actions performed by warden
+create table warden_action_log (
+  id integer not null primary key autoincrement,
+  ts text not null default (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+  -- The operating system user running warden
+  "user" text not null,
+
+  -- Always null, there are no remote clients of an embedded database
+  client_address text null,
+
+  -- The process ID of warden
+  session_pid integer not null,
+
+  the_action text
+);
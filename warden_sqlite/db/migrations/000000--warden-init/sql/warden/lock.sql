@@ -0,0 +1,10 @@
+-- The process holding the lock of the registry (the lock itself is kept on the lock file)
+create table warden_lock (
+  id integer not null primary key check (id = 1),
+
+  -- The process ID of warden
+  pid integer not null,
+
+  -- When the lock has been acquired
+  acquired_at text not null
+);
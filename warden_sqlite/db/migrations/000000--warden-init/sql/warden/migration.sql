@@ -0,0 +1,17 @@
+-- Registered migrations
+create table warden_migration (
+  -- Migration ID
+  id integer not null primary key,
+
+  -- Migration name
+  name text not null,
+
+  -- Registration timestamp
+  sync_ts text not null default (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+
+  -- Migration deployment timestamp (null if it hasn't been deployed)
+  deploy_ts text null default null,
+
+  -- Timestamp of a failed deployment outside of a transaction (the migration may be partially applied)
+  partial_ts text null default null
+);
@@ -0,0 +1,7 @@
+-- Migration revert source (SQL)
+create table warden_migration_revert (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- The SQL to be executed to revert the migration
+  data text
+);
@@ -0,0 +1,16 @@
+-- Migration seal (signature)
+create table warden_migration_seal (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- The seal generation timestamp (RFC 3339)
+  generated_at text not null,
+
+  -- Seal calculation algorithm
+  algo text not null,
+
+  -- Seal value. Signature generated according to the algorithm
+  value blob,
+
+  -- Seal value of the revert source (null if the migration has no revert)
+  revert_value blob null
+);
@@ -0,0 +1,10 @@
+-- Migration snapshots
+create table warden_migration_snapshot (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- Datum format (e.g. .tar.gz)
+  "format" text not null,
+
+  -- The snapshot data
+  data blob
+);
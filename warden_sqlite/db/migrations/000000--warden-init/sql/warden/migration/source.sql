@@ -0,0 +1,7 @@
+-- Migration source (SQL)
+create table warden_migration_source (
+  migration_id integer not null primary key references warden_migration (id) on delete cascade on update cascade,
+
+  -- The actual migration SQL to be executed
+  data text
+);
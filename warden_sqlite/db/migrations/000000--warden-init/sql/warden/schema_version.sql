@@ -0,0 +1,10 @@
+-- Versions of the warden schema (the highest one is the current)
+create table warden_schema_version (
+  -- The internal schema version
+  version integer not null primary key,
+
+  -- When the schema has been upgraded to the version
+  upgrade_ts text not null default (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+insert into warden_schema_version (version) values (1);
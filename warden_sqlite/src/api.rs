@@ -0,0 +1,239 @@
+//! The counterparts of the warden API functions of the Postgres driver.
+//! SQLite has no stored procedures, so they are implemented here.
+
+use chrono::prelude::{DateTime, FixedOffset};
+use failure::Error;
+use rusqlite::{self, params, OptionalExtension};
+use std::env;
+use std::process;
+use warden_core::migration::base36;
+
+use crate::registry::render;
+
+/// The current timestamp in the format of the registry tables
+pub const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')";
+
+pub fn do_register_migration(
+    connection: &rusqlite::Connection,
+    schema: &str,
+    id: u128,
+    name: &str,
+    source: &str,
+    snapshot_format: &str,
+    snapshot_data: &[u8],
+    seal_generated_at: &DateTime<FixedOffset>,
+    seal_algo: &str,
+    seal_data: &[u8],
+    revert_source: Option<&str>,
+    seal_revert_data: Option<&[u8]>,
+) -> Result<(), Error> {
+    let fullname = fullname(id, name);
+
+    let registered: Option<(Option<String>, Option<String>)> = connection
+        .query_row(
+            &render("select deploy_ts, partial_ts from warden_migration where id = ?1", schema),
+            params![id as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    // a partially applied migration stays so until it is completed
+    let partial_ts = match registered {
+        Some((Some(_), _)) => Err(failure::err_msg(format!(
+            "Migration {} has already been deployed",
+            fullname
+        )))?,
+        Some((None, partial_ts)) => {
+            // the migration has been reverted (or partially applied), so we register it anew
+            connection.execute(
+                &render("delete from warden_migration where id = ?1", schema),
+                params![id as i64],
+            )?;
+            do_log(connection, schema, &format!("Unregistered | {}", fullname))?;
+            partial_ts
+        }
+        None => None,
+    };
+
+    connection.execute(
+        &render("insert into warden_migration (id, name, partial_ts) values (?1, ?2, ?3)", schema),
+        params![id as i64, name, partial_ts],
+    )?;
+    connection.execute(
+        &render("insert into warden_migration_source (migration_id, data) values (?1, ?2)", schema),
+        params![id as i64, source],
+    )?;
+    connection.execute(
+        &render(
+            "insert into warden_migration_snapshot (migration_id, format, data) values (?1, ?2, ?3)",
+            schema,
+        ),
+        params![id as i64, snapshot_format, snapshot_data],
+    )?;
+    connection.execute(
+        &render(
+            "insert into warden_migration_seal (migration_id, generated_at, algo, value, revert_value)
+            values (?1, ?2, ?3, ?4, ?5)",
+            schema,
+        ),
+        params![
+            id as i64,
+            seal_generated_at.to_rfc3339(),
+            seal_algo,
+            seal_data,
+            seal_revert_data
+        ],
+    )?;
+
+    if let Some(revert_source) = revert_source {
+        connection.execute(
+            &render("insert into warden_migration_revert (migration_id, data) values (?1, ?2)", schema),
+            params![id as i64, revert_source],
+        )?;
+    }
+
+    do_log(connection, schema, &format!("Registered | {}", fullname))
+}
+
+/// Execute the registered source of the migration and mark it as deployed
+pub fn do_deploy_migration(connection: &rusqlite::Connection, schema: &str, id: u128) -> Result<(), Error> {
+    let source: Option<String> = connection
+        .query_row(
+            &render("select data from warden_migration_source where migration_id = ?1", schema),
+            params![id as i64],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let source = source.ok_or_else(|| unknown_migration(id))?;
+
+    connection.execute_batch(&source)?;
+
+    do_complete_migration(connection, schema, id)
+}
+
+pub fn do_complete_migration(connection: &rusqlite::Connection, schema: &str, id: u128) -> Result<(), Error> {
+    let (fullname, deploy_ts) = get_migration(connection, schema, id)?;
+
+    if deploy_ts.is_some() {
+        Err(failure::err_msg(format!(
+            "Migration {} has already been deployed",
+            fullname
+        )))?
+    }
+
+    connection.execute(
+        &render(
+            &format!(
+                "update warden_migration set deploy_ts = {}, partial_ts = null where id = ?1",
+                NOW
+            ),
+            schema,
+        ),
+        params![id as i64],
+    )?;
+
+    do_log(connection, schema, &format!("Deployed | {}", fullname))
+}
+
+pub fn do_fail_migration(
+    connection: &rusqlite::Connection,
+    schema: &str,
+    id: u128,
+    error: &str,
+) -> Result<(), Error> {
+    let (fullname, _) = get_migration(connection, schema, id)?;
+
+    connection.execute(
+        &render(
+            &format!("update warden_migration set partial_ts = {} where id = ?1", NOW),
+            schema,
+        ),
+        params![id as i64],
+    )?;
+
+    do_log(
+        connection,
+        schema,
+        &format!("Partially applied | {} | {}", fullname, error),
+    )
+}
+
+pub fn do_revert_migration(connection: &rusqlite::Connection, schema: &str, id: u128) -> Result<(), Error> {
+    let (fullname, deploy_ts) = get_migration(connection, schema, id)?;
+
+    if deploy_ts.is_none() {
+        Err(failure::err_msg(format!(
+            "Migration {} has not been deployed",
+            fullname
+        )))?
+    }
+
+    let revert: Option<String> = connection
+        .query_row(
+            &render("select data from warden_migration_revert where migration_id = ?1", schema),
+            params![id as i64],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let revert = revert.ok_or_else(|| {
+        failure::err_msg(format!("Migration {} has no revert source", fullname))
+    })?;
+
+    connection.execute_batch(&revert)?;
+
+    connection.execute(
+        &render("update warden_migration set deploy_ts = null where id = ?1", schema),
+        params![id as i64],
+    )?;
+
+    do_log(connection, schema, &format!("Reverted | {}", fullname))
+}
+
+pub fn do_log(connection: &rusqlite::Connection, schema: &str, action: &str) -> Result<(), Error> {
+    let user = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_default();
+
+    connection.execute(
+        &render(
+            "insert into warden_action_log (\"user\", session_pid, the_action) values (?1, ?2, ?3)",
+            schema,
+        ),
+        params![user, process::id(), action],
+    )?;
+
+    Ok(())
+}
+
+/// The full name and the deployment timestamp of a registered migration
+fn get_migration(
+    connection: &rusqlite::Connection,
+    schema: &str,
+    id: u128,
+) -> Result<(String, Option<String>), Error> {
+    let migration: Option<(String, Option<String>)> = connection
+        .query_row(
+            &render("select name, deploy_ts from warden_migration where id = ?1", schema),
+            params![id as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let (name, deploy_ts) = migration.ok_or_else(|| unknown_migration(id))?;
+
+    Ok((fullname(id, &name), deploy_ts))
+}
+
+pub fn uid(id: u128) -> String {
+    format!("{:0>6}", base36::encode(id))
+}
+
+fn fullname(id: u128, name: &str) -> String {
+    format!("{}--{}", uid(id), name)
+}
+
+fn unknown_migration(id: u128) -> Error {
+    failure::err_msg(format!("Unknown migration with \"{}\"", uid(id)))
+}
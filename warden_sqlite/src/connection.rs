@@ -0,0 +1,659 @@
+use failure::{Error, ResultExt};
+
+use crate::api;
+use crate::lock;
+use crate::registry;
+use chrono::prelude::{DateTime, FixedOffset, Utc};
+use path_abs::FileRead;
+use regex::Regex;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{self, params, Batch, OptionalExtension};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use warden_core::dbms::{ActionLogEntry, ActionLogFilter, HistoryEntry, HistoryFilter, Registration, Timeouts};
+use warden_core::migration::snapshot::{self, Snapshot};
+use warden_core::migration::transaction::TransactionMode;
+use warden_core::migration::{algo::Algo, identity::Identity, seal::Seal};
+use warden_core::{dbms, migration::meta::Meta};
+
+/// The version of the warden tables the initial migration creates
+const SCHEMA_VERSION: i32 = 1;
+
+/// Name, source, seal timestamp, algo, sign and revert sign
+type RegistrationRow = (String, Option<String>, String, String, Vec<u8>, Option<Vec<u8>>);
+
+#[derive(Debug)]
+pub struct Connection {
+    connection: rusqlite::Connection,
+
+    /// The database file path
+    catalog: String,
+
+    /// The prefix of the warden tables
+    schema: String,
+
+    lock: RefCell<Option<lock::Lock>>,
+}
+
+impl dbms::Connection for Connection {
+    fn get_catalog(&self) -> &str {
+        &self.catalog
+    }
+
+    fn get_last_deployed_migration(&self) -> Result<Option<u128>, Error> {
+        if !self.is_ready()? {
+            return Ok(None);
+        }
+
+        let result: Option<i64> = self.connection.query_row(
+            &self.render("select max(id) from warden_migration where deploy_ts is not null"),
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(result.map(|id| id as u128))
+    }
+
+    fn get_registered_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        if !self.is_ready()? {
+            return Ok(BTreeSet::new());
+        }
+
+        self.query_ids("select id from warden_migration")
+    }
+
+    fn get_deployed_migrations(&self) -> Result<BTreeSet<u128>, Error> {
+        if !self.is_ready()? {
+            return Ok(BTreeSet::new());
+        }
+
+        self.query_ids("select id from warden_migration where deploy_ts is not null")
+    }
+
+    fn get_registered_migration(&self, id: u128) -> Result<Option<Registration>, Error> {
+        if !self.is_ready()? {
+            return Ok(None);
+        }
+
+        let row: Option<RegistrationRow> = self
+            .connection
+            .query_row(
+                &self.render(
+                    "select
+                      m.name,
+                      s.data,
+                      l.generated_at,
+                      l.algo,
+                      l.value,
+                      l.revert_value
+                    from
+                      warden_migration m
+                    join
+                      warden_migration_source s on s.migration_id = m.id
+                    join
+                      warden_migration_seal l on l.migration_id = m.id
+                    where
+                      m.id = ?1
+                    ",
+                ),
+                params![id as i64],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let (name, source, generated_at, algo, sign, revert_sign) = if let Some(row) = row {
+            row
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(Registration {
+            identity: Identity::build(api::uid(id), name),
+            source: source.unwrap_or_default(),
+            seal: Seal {
+                timestamp: parse_ts(&generated_at)?,
+                algo: Algo::from_str(&algo)
+                    .ok_or(failure::err_msg(format!("unknown algo {}", algo)))?,
+                sign: sign,
+                revert_sign: revert_sign,
+            },
+        }))
+    }
+
+    fn get_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, Error> {
+        if !self.is_ready()? {
+            return Ok(Vec::new());
+        }
+
+        let mut statement = self.connection.prepare(&self.render(
+            "select
+              h.id,
+              h.name,
+              h.sync_ts,
+              h.deploy_ts,
+              h.partial_ts,
+              h.algo,
+              h.value
+            from (
+              select
+                m.id,
+                m.name,
+                m.sync_ts,
+                m.deploy_ts,
+                m.partial_ts,
+                l.algo,
+                l.value
+              from
+                warden_migration m
+              left join
+                warden_migration_seal l on l.migration_id = m.id
+              where
+                (?1 is null or m.sync_ts >= ?1 or m.deploy_ts >= ?1)
+              and
+                (not ?2 or m.deploy_ts is null)
+              order by
+                m.id desc
+              limit
+                coalesce(?3, -1)
+            ) h
+            order by
+              h.id
+            ",
+        ))?;
+
+        let mut rows = statement.query(params![
+            filter.since.as_ref().map(format_ts),
+            filter.pending,
+            filter.limit
+        ])?;
+
+        let mut result = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let deployed_at: Option<String> = row.get(3)?;
+            let partially_applied_at: Option<String> = row.get(4)?;
+
+            result.push(HistoryEntry {
+                identity: Identity::build(api::uid(id as u128), row.get(1)?),
+                registered_at: parse_ts(&row.get::<_, String>(2)?)?,
+                deployed_at: deployed_at.as_ref().map(|ts| parse_ts(ts)).transpose()?,
+                partially_applied_at: partially_applied_at
+                    .as_ref()
+                    .map(|ts| parse_ts(ts))
+                    .transpose()?,
+                seal_algo: row.get(5)?,
+                seal_sign: row.get(6)?,
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn get_migration_snapshot(&self, id: u128) -> Result<Option<Snapshot>, Error> {
+        if !self.is_ready()? {
+            return Ok(None);
+        }
+
+        let row: Option<(String, Vec<u8>)> = self
+            .connection
+            .query_row(
+                &self.render("select format, data from warden_migration_snapshot where migration_id = ?1"),
+                params![id as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        let (format, data) = if let Some(row) = row {
+            row
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(Snapshot {
+            format: snapshot::Format::from(&format)?,
+            data: data,
+        }))
+    }
+
+    fn lock(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        if self.lock.borrow().is_some() || self.catalog == ":memory:" {
+            // nobody else can reach an in-memory database
+            return Ok(());
+        }
+
+        let lock = lock::acquire(&self.connection, &self.catalog, &self.schema, timeout)?;
+        *self.lock.borrow_mut() = Some(lock);
+
+        Ok(())
+    }
+
+    fn unlock(&self) -> Result<(), Error> {
+        if let Some(lock) = self.lock.borrow_mut().take() {
+            lock::release(lock)?;
+        }
+
+        Ok(())
+    }
+
+    fn deploy(&self, meta: Meta, timeouts: &Timeouts) -> Result<(), Error> {
+        if !self.is_ready()? {
+            let id = meta.get_identity().get_id().unwrap_or(1);
+
+            if id == 0 {
+                return self.deploy_initial(meta);
+            } else {
+                return Err(failure::err_msg(
+                    format!(
+                        r#"The database is not initialised with Warden. Error trying to deploy migration "{}". The initial migration must be deployed first"#,
+                        meta.get_identity()
+                    )
+                ));
+            }
+        }
+
+        warn_timeouts(&timeouts.for_migration(&meta));
+
+        if meta.get_transaction() == TransactionMode::None {
+            return self.deploy_non_transactional(meta);
+        }
+
+        let transaction = self.connection.unchecked_transaction()?;
+        self.register_migration(&transaction, &meta)?;
+        self.deploy_migration(&transaction, &meta)?;
+        Ok(transaction.commit()?)
+    }
+
+    fn deploy_atomic(&self, migrations: Vec<Meta>, timeouts: &Timeouts) -> Result<(), (usize, Error)> {
+        if let Some(position) = migrations
+            .iter()
+            .position(|meta| meta.get_transaction() == TransactionMode::None)
+        {
+            return Err((
+                position,
+                failure::err_msg(format!(
+                    r#"Migration "{}" is not transactional and cannot be deployed atomically"#,
+                    migrations[position].get_identity()
+                )),
+            ));
+        }
+
+        let initialised = self.is_ready().map_err(|error| (0, error))?;
+
+        if let Some(meta) = migrations.first() {
+            if !initialised && meta.get_identity().get_id() != Some(0) {
+                return Err((
+                    0,
+                    failure::err_msg(format!(
+                        r#"The database is not initialised with Warden. Error trying to deploy migration "{}". The initial migration must be deployed first"#,
+                        meta.get_identity()
+                    )),
+                ));
+            }
+        }
+
+        for meta in &migrations {
+            warn_timeouts(&timeouts.for_migration(meta));
+        }
+
+        let mut failed = 0;
+
+        let result = (|| {
+            let transaction = self.connection.unchecked_transaction()?;
+
+            for (position, meta) in migrations.iter().enumerate() {
+                failed = position;
+
+                if meta.get_identity().get_id() == Some(0) {
+                    self.deploy_initial_within(&transaction, meta)?;
+                    continue;
+                }
+
+                self.register_migration(&transaction, meta)?;
+                self.deploy_migration(&transaction, meta)?;
+            }
+
+            failed = migrations.len();
+            Ok(transaction.commit()?)
+        })();
+
+        result.map_err(|error| (failed, error))
+    }
+
+    fn upgrade(&self) -> Result<Vec<String>, Error> {
+        if !self.is_ready()? {
+            return Err(failure::err_msg(
+                "The database is not initialised with Warden, deploy the initial migration instead",
+            ));
+        }
+
+        // there have been no upgrades of the warden tables yet
+        Ok(Vec::new())
+    }
+
+    fn log_action(&self, action: &str) -> Result<(), Error> {
+        if !self.is_initialised()? {
+            log::warn!("The database is not initialised with Warden, could not log: {}", action);
+            return Ok(());
+        }
+
+        api::do_log(&self.connection, &self.schema, action)
+    }
+
+    fn get_action_log(&self, filter: &ActionLogFilter) -> Result<Vec<ActionLogEntry>, Error> {
+        if !self.is_ready()? {
+            return Ok(Vec::new());
+        }
+
+        let mut statement = self.connection.prepare(&self.render(
+            "select
+              l.id,
+              l.ts,
+              l.\"user\",
+              l.client_address,
+              l.session_pid,
+              l.the_action
+            from
+              warden_action_log l
+            where
+              (?1 is null or l.ts >= ?1)
+            and
+              (?2 is null or l.\"user\" = ?2)
+            and
+              (?3 is null or l.the_action regexp ?3)
+            and
+              (?4 is null or l.id > ?4)
+            order by
+              l.id
+            ",
+        ))?;
+
+        let mut rows = statement.query(params![
+            filter.since.as_ref().map(format_ts),
+            filter.user,
+            filter.grep,
+            filter.after_id
+        ])?;
+
+        let mut result = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            result.push(ActionLogEntry {
+                id: row.get(0)?,
+                ts: parse_ts(&row.get::<_, String>(1)?)?,
+                user: row.get(2)?,
+                client_address: row.get(3)?,
+                session_pid: row.get(4)?,
+                action: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn revert(&self, meta: Meta) -> Result<(), Error> {
+        let id = meta
+            .get_identity()
+            .get_id()
+            .ok_or(failure::err_msg("could not decode migration id"))?;
+
+        if id == 0 {
+            return Err(failure::err_msg("The initial migration cannot be reverted"));
+        }
+
+        if !self.is_ready()? {
+            return Err(failure::err_msg(format!(
+                r#"The database is not initialised with Warden. Error trying to revert migration "{}""#,
+                meta.get_identity()
+            )));
+        }
+
+        let transaction = self.connection.unchecked_transaction()?;
+        api::do_revert_migration(&transaction, &self.schema, id)?;
+        Ok(transaction.commit()?)
+    }
+}
+
+impl Connection {
+    fn is_initialised(&self) -> Result<bool, Error> {
+        let result: bool = self.connection.query_row(
+            "select count(*) = 1 from sqlite_master where type = 'table' and name = ?1",
+            params![format!("{}_migration", self.schema)],
+            |row| row.get(0),
+        )?;
+
+        Ok(result)
+    }
+
+    /// Whether the warden tables can be queried, i.e. they are created
+    /// and of the supported version
+    fn is_ready(&self) -> Result<bool, Error> {
+        if !self.is_initialised()? {
+            return Ok(false);
+        }
+
+        let version: Option<i32> = self.connection.query_row(
+            &self.render("select max(version) from warden_schema_version"),
+            [],
+            |row| row.get(0),
+        )?;
+
+        let version = version.unwrap_or(0);
+
+        if version > SCHEMA_VERSION {
+            Err(failure::err_msg(format!(
+                "The warden schema is at version {} which is newer than this warden supports ({}), please upgrade warden",
+                version, SCHEMA_VERSION
+            )))?
+        }
+
+        Ok(true)
+    }
+
+    /// The warden SQL rendered against the registry schema
+    fn render<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        registry::render(sql, &self.schema)
+    }
+
+    fn query_ids(&self, query: &str) -> Result<BTreeSet<u128>, Error> {
+        let mut statement = self.connection.prepare(&self.render(query))?;
+        let mut rows = statement.query([])?;
+
+        let mut result = BTreeSet::new();
+
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            result.insert(id as u128);
+        }
+
+        Ok(result)
+    }
+
+    fn deploy_migration(&self, connection: &rusqlite::Connection, meta: &Meta) -> Result<(), Error> {
+        let id = meta
+            .get_identity()
+            .get_id()
+            .ok_or(failure::err_msg("could not decode migration id"))?;
+
+        // the database is embedded, so the server and the client executions are the same
+        api::do_deploy_migration(connection, &self.schema, id)
+            .context(format!(r#"Migration "{}""#, meta.get_identity()))?;
+
+        Ok(())
+    }
+
+    fn register_migration(&self, connection: &rusqlite::Connection, meta: &Meta) -> Result<(), Error> {
+        let snapshot = snapshot::Snapshot::take(snapshot::Format::TarGz, meta)?;
+        let seal = meta.get_seal_meta().read_the_seal()?;
+
+        let revert_source = if let Some(revert) = meta.get_revert() {
+            Some(FileRead::read(revert.get_target())?.read_string()?)
+        } else {
+            None
+        };
+
+        api::do_register_migration(
+            connection,
+            &self.schema,
+            meta.get_identity()
+                .get_id()
+                .ok_or(failure::err_msg("could not decode migration id"))?,
+            meta.get_identity().get_name(),
+            &FileRead::read(meta.get_target())?.read_string()?,
+            snapshot.format.as_str(),
+            &snapshot.data,
+            &seal.timestamp,
+            seal.algo.stringify(),
+            &seal.sign,
+            revert_source.as_ref().map(|s| s.as_str()),
+            seal.revert_sign.as_ref().map(|s| s.as_slice()),
+        )
+    }
+
+    /// Register the migration in its own transaction and then execute
+    /// its statements one by one, each of them committed on its own
+    fn deploy_non_transactional(&self, meta: Meta) -> Result<(), Error> {
+        let id = meta
+            .get_identity()
+            .get_id()
+            .ok_or(failure::err_msg("could not decode migration id"))?;
+
+        let transaction = self.connection.unchecked_transaction()?;
+        self.register_migration(&transaction, &meta)?;
+        transaction.commit()?;
+
+        let source = FileRead::read(meta.get_target())?.read_string()?;
+
+        let mut batch = Batch::new(&self.connection, &source);
+        let mut num = 0;
+
+        loop {
+            num += 1;
+
+            let result = batch.next().and_then(|statement| {
+                if let Some(mut statement) = statement {
+                    let mut rows = statement.raw_query();
+                    while rows.next()?.is_some() {}
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            });
+
+            match result {
+                Ok(true) => continue,
+                Ok(false) => break,
+                Err(error) => {
+                    let error = format!("statement {}: {}", num, error);
+
+                    // a bookkeeping failure is only logged, so that it never hides the error
+                    if let Err(fail_error) = api::do_fail_migration(&self.connection, &self.schema, id, &error) {
+                        log::error!(
+                            r#"Could not mark migration "{}" as partially applied: {}"#,
+                            meta.get_identity(),
+                            fail_error
+                        );
+                    }
+
+                    return Err(failure::err_msg(format!(
+                        r#"Migration "{}" has been partially applied, {}"#,
+                        meta.get_identity(),
+                        error
+                    )));
+                }
+            }
+        }
+
+        api::do_complete_migration(&self.connection, &self.schema, id)
+    }
+
+    fn deploy_initial(&self, meta: Meta) -> Result<(), Error> {
+        log::trace!("Deploying initial migration");
+        let transaction = self.connection.unchecked_transaction()?;
+        self.deploy_initial_within(&transaction, &meta)?;
+
+        Ok(transaction.commit()?)
+    }
+
+    fn deploy_initial_within(&self, connection: &rusqlite::Connection, meta: &Meta) -> Result<(), Error> {
+        let sql = &FileRead::read(meta.get_target())?.read_string()?;
+        connection.execute_batch(&self.render(sql))?;
+
+        self.register_migration(connection, meta)?;
+        connection.execute(
+            &self.render(&format!(
+                "update warden_migration set deploy_ts = {} where id = 0",
+                api::NOW
+            )),
+            [],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Open the database file of the URL (sqlite://path, sqlite:path or the path itself)
+pub fn open(url: &str, schema: &str) -> Result<Connection, Error> {
+    if !registry::is_valid_schema(schema) {
+        Err(failure::err_msg(format!(
+            "Invalid registry schema \"{}\", it must be a lowercase identifier",
+            schema
+        )))?
+    }
+
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+        .unwrap_or(url);
+
+    let connection = rusqlite::Connection::open(path)?;
+
+    connection.execute_batch("pragma foreign_keys = on")?;
+
+    connection.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |context| {
+            let pattern = Regex::new(&context.get::<String>(0)?)
+                .map_err(|error| rusqlite::Error::UserFunctionError(Box::new(error)))?;
+            let value = context.get::<Option<String>>(1)?;
+
+            Ok(value.map(|value| pattern.is_match(&value)).unwrap_or(false))
+        },
+    )?;
+
+    Ok(Connection {
+        connection: connection,
+        catalog: path.to_string(),
+        schema: schema.to_string(),
+        lock: RefCell::new(None),
+    })
+}
+
+/// The timestamps are kept as RFC 3339 strings in UTC, so that they can be compared
+fn format_ts(ts: &DateTime<FixedOffset>) -> String {
+    ts.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+fn parse_ts(ts: &str) -> Result<DateTime<FixedOffset>, Error> {
+    Ok(DateTime::parse_from_rfc3339(ts)
+        .with_context(|_| format!("Invalid timestamp \"{}\"", ts))?)
+}
+
+fn warn_timeouts(timeouts: &Timeouts) {
+    if timeouts.lock_timeout.is_some() || timeouts.statement_timeout.is_some() {
+        log::warn!("The lock and statement timeouts are not supported by SQLite, ignoring them");
+    }
+}
@@ -0,0 +1,123 @@
+//! The lock guarding the warden tables from concurrent modifications.
+//!
+//! SQLite has no advisory locks, so the lock is a write transaction held open
+//! on a separate lock file next to the database (`<database>-<schema>.lock`).
+//! The operating system releases it along with the process, so it cannot be
+//! left behind by a crashed deployment. The holder is also recorded in the
+//! registry (once initialised), only to tell the waiting processes who they wait for.
+
+use failure::Error;
+use rusqlite::{self, params, ErrorCode, OptionalExtension};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::api::NOW;
+use crate::registry::render;
+
+/// How often to retry while waiting for the lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The lock being held, released when dropped
+#[derive(Debug)]
+pub struct Lock {
+    guard: rusqlite::Connection,
+}
+
+/// Acquire the lock of the registry schema of the database file.
+/// Waits indefinitely when no timeout given, otherwise gives up once
+/// the timeout expires (zero timeout means to not wait at all)
+pub fn acquire(
+    connection: &rusqlite::Connection,
+    path: &str,
+    schema: &str,
+    timeout: Option<Duration>,
+) -> Result<Lock, Error> {
+    let guard = rusqlite::Connection::open(format!("{}-{}.lock", path, schema))?;
+    guard.busy_timeout(Duration::from_secs(0))?;
+
+    if try_acquire(&guard)? {
+        return hold(connection, schema, guard);
+    }
+
+    log::info!("Waiting for the lock held by {}", describe_holder(connection, schema)?);
+
+    let started = Instant::now();
+
+    while timeout.map(|timeout| started.elapsed() < timeout).unwrap_or(true) {
+        thread::sleep(RETRY_INTERVAL);
+
+        if try_acquire(&guard)? {
+            return hold(connection, schema, guard);
+        }
+    }
+
+    Err(failure::err_msg(format!(
+        "Could not acquire the lock on the \"{}\" registry, it is held by {}",
+        schema,
+        describe_holder(connection, schema)?
+    )))
+}
+
+pub fn release(lock: Lock) -> Result<(), Error> {
+    Ok(lock.guard.execute_batch("rollback")?)
+}
+
+fn try_acquire(guard: &rusqlite::Connection) -> Result<bool, Error> {
+    match guard.execute_batch("begin immediate") {
+        Ok(()) => Ok(true),
+        Err(rusqlite::Error::SqliteFailure(ref error, _))
+            if error.code == ErrorCode::DatabaseBusy || error.code == ErrorCode::DatabaseLocked =>
+        {
+            Ok(false)
+        }
+        Err(error) => Err(error)?,
+    }
+}
+
+/// Record the process as the holder of the lock (unless the registry is not initialised yet)
+fn hold(connection: &rusqlite::Connection, schema: &str, guard: rusqlite::Connection) -> Result<Lock, Error> {
+    if is_recorded(connection, schema)? {
+        connection.execute(
+            &render(
+                &format!("insert or replace into warden_lock (id, pid, acquired_at) values (1, ?1, {})", NOW),
+                schema,
+            ),
+            params![process::id()],
+        )?;
+    }
+
+    Ok(Lock { guard: guard })
+}
+
+/// Whether the registry keeps the holder of the lock (it is created with the initial migration)
+fn is_recorded(connection: &rusqlite::Connection, schema: &str) -> Result<bool, Error> {
+    let recorded: bool = connection.query_row(
+        "select count(*) = 1 from sqlite_master where type = 'table' and name = ?1",
+        params![format!("{}_lock", schema)],
+        |row| row.get(0),
+    )?;
+
+    Ok(recorded)
+}
+
+/// Human readable description of the process holding the lock
+fn describe_holder(connection: &rusqlite::Connection, schema: &str) -> Result<String, Error> {
+    let holder: Option<(u32, String)> = if is_recorded(connection, schema)? {
+        connection
+            .query_row(
+                &render("select pid, acquired_at from warden_lock where id = 1", schema),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+    } else {
+        None
+    };
+
+    Ok(if let Some((pid, acquired_at)) = holder {
+        format!("process {} (since {})", pid, acquired_at)
+    } else {
+        "an unknown process".to_string()
+    })
+}
@@ -0,0 +1,77 @@
+//! Rendering the warden SQL against the configured registry schema.
+//!
+//! SQLite has no schemas, so the registry schema is used as the prefix
+//! of the warden tables. The SQL is written against the `warden_` prefix,
+//! which gets replaced with the configured one right before execution.
+
+use std::borrow::Cow;
+
+/// The table prefix the SQL is written against
+const CANONICAL: &str = "warden_";
+
+/// Replace the canonical prefix of the table names with the given schema
+pub fn render<'a>(sql: &'a str, schema: &str) -> Cow<'a, str> {
+    if schema == &CANONICAL[..CANONICAL.len() - 1] {
+        return Cow::Borrowed(sql);
+    }
+
+    let bytes = sql.as_bytes();
+    let mut result = String::with_capacity(sql.len());
+    let mut start = 0;
+
+    for (pos, _) in sql.match_indices(CANONICAL) {
+        if pos > 0 && is_ident_char(bytes[pos - 1]) {
+            continue;
+        }
+
+        result.push_str(&sql[start..pos]);
+        result.push_str(schema);
+        result.push('_');
+        start = pos + CANONICAL.len();
+    }
+
+    if start == 0 {
+        return Cow::Borrowed(sql);
+    }
+
+    result.push_str(&sql[start..]);
+
+    Cow::Owned(result)
+}
+
+/// Whether the name can be used as the table prefix without quoting
+pub fn is_valid_schema(name: &str) -> bool {
+    let bytes = name.as_bytes();
+
+    !bytes.is_empty()
+        && bytes.len() < 48
+        && !bytes[0].is_ascii_digit()
+        && bytes.iter().all(|c| is_ident_char(*c) && !c.is_ascii_uppercase())
+}
+
+fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prefix() {
+        assert_eq!(
+            render(
+                "insert into warden_migration (id) select id from my_warden_x; -- warden schema",
+                "app"
+            ),
+            "insert into app_migration (id) select id from my_warden_x; -- warden schema"
+        );
+    }
+
+    #[test]
+    fn test_render_canonical() {
+        let sql = "select id from warden_migration";
+
+        assert_eq!(render(sql, "warden"), sql);
+    }
+}